@@ -8,6 +8,9 @@ local quickEvents = commsLib.quickEvents
 local prevCommands = {}
 local prevCommandsNextIndex = 1
 
+-- the direction the player last moved in, what "in front of the player" means for rotate
+local facing = "north"
+
 
 -- commands
 
@@ -27,20 +30,20 @@ local function alias(input)
         aliases[alia] = val
     end
 
-    local newConfig = Core.getJSON("resources\\config\\config.json")
+    local newConfig = Core.getJSON("resources/config/config.json")
     newConfig["conveyor_line_core_config"]["direction_aliases"] = aliases
-    Core.setJSON("resources\\config\\config.json", newConfig)
+    Core.setJSON("resources/config/config.json", newConfig)
 end
 
 --[[
     help (str)?
   ]]
 local function help(input)
-    
+    Core.log("info", Core.Commands.help(input.arguments[1]))
 end
 
 --[[
-    place str dir num
+    place str dir num dir?
   ]]
   local function place(input)
     local player = Core.GameInfo.Player
@@ -65,6 +68,12 @@ end
     if not tile then return "isn't tile type" end
     if not commsLib.isDirection(direction) then return "isn't direction" end
 
+    local tileFacing = input.arguments[4]
+    if tileFacing and aliases[tileFacing] then
+        tileFacing = aliases[tileFacing]
+    end
+    if tileFacing and not commsLib.isDirection(tileFacing) then return "facing isn't direction" end
+
     local distance = 1
     local inputDistance = tonumber(input.arguments[3])
     if inputDistance then
@@ -83,7 +92,7 @@ end
     local canPlace = movementCapability.validity == "noObstruction" or obstructionAtPlayer
 
     if canPlace and distance > 0 or not tileTypes.get(tile).solid then
-        map.setFromId(target.x, target.y, tile)
+        map.setFromId(target.x, target.y, tile, "place", tileFacing)
         Core.bufferMapRedraw()
     end
 end
@@ -124,7 +133,7 @@ local function breakCommand(input)
             return
         end
 
-        map.setFromId(target.x, target.y, airId)
+        map.setFromId(target.x, target.y, airId, "break")
         Core.bufferMapRedraw()
     end
 end
@@ -145,6 +154,7 @@ local function move(input)
     end
 
     if not commsLib.isDirection(direction) then return "isn't direction" end
+    facing = direction
 
     local distance = 1
     local inputDistance = tonumber(input.arguments[2])
@@ -169,6 +179,34 @@ local function move(input)
     end
 end
 
+--[[
+    rotate dir? num?
+  ]]
+local function rotate(input)
+    local map = Core.GameInfo.Map.TileMap
+
+    local direction = input.arguments[1] or facing
+    if aliases[direction] then
+        direction = aliases[direction]
+    end
+
+    if not commsLib.isDirection(direction) then return "isn't direction" end
+
+    local turns = 1
+    local inputTurns = tonumber(input.arguments[2])
+    if inputTurns then
+        turns = inputTurns
+    end
+
+    local target = commsLib.findObstructableTarget(direction, 1).target
+    if not target then return "nothing to rotate there" end
+
+    local rotated, message = map.rotate(target.x, target.y, turns)
+    if not rotated then return message end
+
+    Core.bufferMapRedraw()
+end
+
 --[[
     set (key |a {key; a}) {com; b}- ({val; ?a}- |a {{{val; ?a}; b}; a})
     ?a: amount of arguments which com[i] takes
@@ -249,19 +287,53 @@ end
 local function timeTravel(input)
     if not input.arguments[1] then return "amount missing" end
 
-    Core.Tick.addTime(tonumber(input.arguments[1]))
+    Core.Tick.step(tonumber(input.arguments[1]))
 end
 
--- the command names for the user end
-local commandFunctions = {
-    -- help
-    ["help"] = help,
-    -- fundamentals
-    ["set"] = set, ["move"] = move,  ["break"] = breakCommand, ["place"] = place,
-    ["alias"] = alias,
-    -- debug
-    ["print"] = printCommand, ["reload"] = reload, ["time-travel"] = timeTravel
-}
+--[[
+    pause
+  ]]
+local function pause()
+    local paused = not Core.Tick.isPaused()
+    Core.Tick.setPaused(paused)
+
+    if paused then
+        Core.log("info", "paused at tick " .. Core.Tick.current())
+    else
+        Core.log("info", "resumed at tick " .. Core.Tick.current())
+    end
+end
+
+--[[
+    speed num?
+  ]]
+local function speed(input)
+    local inputSpeed = input.arguments[1]
+    if not inputSpeed then
+        Core.log("info", "speed is " .. Core.Tick.getSpeed())
+        return
+    end
+
+    local newSpeed = tonumber(inputSpeed)
+    if not newSpeed then return "isn't number" end
+
+    local changed, message = Core.Tick.setSpeed(newSpeed)
+    if not changed then return message end
+end
+
+--[[
+    step num?
+  ]]
+local function step(input)
+    local amount = 1
+    local inputAmount = tonumber(input.arguments[1])
+    if inputAmount then
+        amount = inputAmount
+    end
+    if amount < 0 or amount % 1 ~= 0 then return "amount must be a whole number, 0 or more" end
+
+    Core.Tick.step(amount)
+end
 
 local function getWithWrapAround(num, min, max)
     if num > max then return min + num - max end
@@ -271,12 +343,12 @@ end
 
 -- used in the event listeners to call commands and print their early return fail messages
 local function runCommand(command, input)
-    local output = command(input)
+    local output = Core.Commands.run(command, input)
     if output then
-        Core.print(output)
+        Core.log("warn", output)
     end
 
-    prevCommands[prevCommandsNextIndex] = {command = command, input = input}
+    prevCommands[prevCommandsNextIndex] = {command = command, arguments = input.arguments}
     prevCommandsNextIndex = prevCommandsNextIndex + 1
 
     prevCommandsNextIndex = getWithWrapAround(prevCommandsNextIndex+1, 1, 10)
@@ -310,20 +382,111 @@ local function previous(input)
     if argsSource then toRun.arguments = input1 end
   end
 
+  if not toRun then return "no previous command" end
+
   debug.deepPrintTable(toRun)
   runCommand(toRun.command, toRun)
 end
-commandFunctions["p"] = previous
-commandFunctions["prev"] = previous
-commandFunctions["previous"] = previous
+
+-- the command names for the user end
+local directions = {"north", "east", "south", "west"}
+
+-- help
+Core.Commands.register("help", {
+    description = "lists every command, or shows how to use one",
+    args = {{name = "command", type = "string", optional = true}},
+    handler = help
+})
+-- fundamentals
+Core.Commands.register("set", {
+    usage = "set (key | {keys}) (command | {commands}) (args | {{args}})",
+    description = "binds keys to commands, set <key> none unbinds",
+    args = {{name = "keys"}, {name = "commands"}, {name = "arguments", optional = true}},
+    handler = set
+})
+Core.Commands.register("move", {
+    description = "moves the player, stopping at anything solid",
+    args = {
+        {name = "direction", type = "string", completions = directions},
+        {name = "distance", type = "number", optional = true}
+    },
+    handler = move
+})
+Core.Commands.register("break", {
+    description = "replaces the tile at a distance with air",
+    args = {
+        {name = "direction", type = "string", completions = directions},
+        {name = "distance", type = "number", optional = true}
+    },
+    handler = breakCommand
+})
+Core.Commands.register("place", {
+    description = "places a tile at a distance",
+    args = {
+        {name = "tile", type = "tile"},
+        {name = "direction", type = "string", completions = directions},
+        {name = "distance", type = "number", optional = true},
+        {name = "facing", type = "string", completions = directions, optional = true}
+    },
+    handler = place
+})
+Core.Commands.register("rotate", {
+    description = "turns the tile next to the player clockwise, in front of the player by default",
+    args = {
+        {name = "direction", type = "string", completions = directions, optional = true},
+        {name = "turns", type = "number", optional = true}
+    },
+    handler = rotate
+})
+Core.Commands.register("alias", {
+    description = "makes a word stand in for a direction, alias <word> none removes it",
+    args = {{name = "alias", type = "string"}, {name = "direction", type = "string", completions = directions}},
+    handler = alias
+})
+Core.Commands.register("previous", {
+    usage = "previous [n] [{args}]",
+    description = "runs one of the last 10 commands again, optionally with new arguments",
+    args = {{name = "n", optional = true}, {name = "args", optional = true}},
+    aliases = {"p", "prev"},
+    handler = previous
+})
+-- debug
+Core.Commands.register("print", {
+    description = "writes a value to the message log",
+    args = {{name = "value"}},
+    handler = printCommand
+})
+Core.Commands.register("reload", {
+    description = "reloads every module script, entities are removed since their state is lost",
+    handler = reload
+})
+Core.Commands.register("time-travel", {
+    description = "skips ahead by an amount of ticks",
+    args = {{name = "amount", type = "number"}},
+    handler = timeTravel
+})
+-- time
+Core.Commands.register("pause", {
+    description = "pauses or resumes ticks",
+    handler = pause
+})
+Core.Commands.register("speed", {
+    description = "shows or sets how fast ticks run, 1 is normal speed",
+    args = {{name = "multiplier", type = "number", optional = true}},
+    handler = speed
+})
+Core.Commands.register("step", {
+    description = "runs ticks one at a time, also while paused",
+    args = {{name = "amount", type = "number", optional = true}},
+    handler = step
+})
 
 -- typed command event listener
 local function commandEvent(input)
     local input = commsLib.typicalSplitInput(input)
 
-    if input.command then
-        local command = commandFunctions[input.command]
-        if command then runCommand(command, input) end
+    if input.command and input.command ~= "" then
+        runCommand(input.command, input)
     end
 end
 
@@ -334,14 +497,12 @@ local function quickCommand(keyEvent)
     local quickEvent = quickEvents[keyCode]
     if quickEvent then
         for i, quickComm in ipairs(quickEvent.commands) do
-            local command = commandFunctions[quickComm]
-
-            if command then 
+            if Core.Commands.exists(quickComm) then 
                 local inputFromQuickEvent = {
                     command = quickComm,
                     arguments = quickEvent.arguments[i]
                 }
-                runCommand(command, inputFromQuickEvent)
+                runCommand(quickComm, inputFromQuickEvent)
             end
         end
     end
@@ -147,25 +147,19 @@ local function findObstructableTarget(direction, distance)
 
     local i = 1;
     for coord = iter.s, iter.e, iter.m do
-        local targettedTile = nil
+        local position = nil
         if horizontal then
-            targettedTile = gameInfo.Map.TileMap.get(coord, playerPos.y)
+            position = {x = coord, y = playerPos.y}
         else
-            targettedTile = gameInfo.Map.TileMap.get(playerPos.x, coord)
+            position = {x = playerPos.x, y = coord}
         end
 
-        if gameInfo.Tile.Types.get(targettedTile.type).solid then
-            local obstructionPosition = nil
-            if horizontal then
-                obstructionPosition = {x = coord, y = playerPos.y}
-            else
-                obstructionPosition = {x = playerPos.x, y = coord}
-            end
-
+        -- ground, tiles and entities can all be in the way
+        if not gameInfo.Map.isPassable(position.x, position.y) then
             if i == distance then
-                return {validity = "obstructionAtTarget", obstructionPosition = obstructionPosition, target = target}
+                return {validity = "obstructionAtTarget", obstructionPosition = position, target = target}
             end
-            return {validity = "obstructionFound", obstructionPosition = obstructionPosition, target = target}
+            return {validity = "obstructionFound", obstructionPosition = position, target = target}
         end
         i = i + 1;
     end
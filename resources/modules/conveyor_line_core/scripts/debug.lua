@@ -8,15 +8,15 @@ local function deepPrintTable(tabl, tabsForDebug)
         end
 
         if type(val) == "table" then
-            Core.print(tabs.."["..tostring(key).."] = {")
+            Core.log("debug", tabs.."["..tostring(key).."] = {")
             deepPrintTable(val, tabsForDebug + 1)
-            Core.print(tabs.."},")
+            Core.log("debug", tabs.."},")
         else
             if type(val) == "string" then
                 val = "\""..val.."\""
             end
             
-            Core.print(tabs.."["..tostring(key).."] = "..tostring(val)..",")
+            Core.log("debug", tabs.."["..tostring(key).."] = "..tostring(val)..",")
         end
     end
 end
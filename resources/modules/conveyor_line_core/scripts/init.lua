@@ -1,4 +1,4 @@
-local config = Core.getJSON("resources\\config\\config.json")["conveyor_line_core_config"]
+local config = Core.getJSON("resources/config/config.json")["conveyor_line_core_config"]
 
 return {
     config = config
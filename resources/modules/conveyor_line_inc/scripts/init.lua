@@ -1,4 +1,4 @@
-local conveyorLineCoresIndex = #Core.InitializationInfo.GameData+1
-Core.InitializationInfo.GameData[conveyorLineCoresIndex] = {}
+local source = "conveyor_line_inc"
+Core.InitializationInfo.GameData[source] = Core.InitializationInfo.GameData[source] or {}
 
-return {conveyorLineCoresIndex = conveyorLineCoresIndex}
\ No newline at end of file
+return {source = source}
\ No newline at end of file
@@ -27,26 +27,25 @@ local function material_gen()
         }
     }
 
+    local gameData = Core.InitializationInfo.GameData[init.source]
+
+    -- each material is a template the metals extend with their colors
+    for i = 1, #material_types do
+        gameData[material_types[i]] = {
+            template = true,
+            item = {},
+            visual_data = material_visuals[material_types[i]]
+        }
+    end
+
     for i = 1, #material_types do
 
         for j = 1, #metal_names do
             local identifier = metal_names[j].."_"..material_types[i]
 
-            local item = {}
-
-            local visual_data = {
-                character_left = material_visuals[material_types[i]]["character_left"],
-                --character_right = material_visuals[material_types[i]]["character_right"],
-
-                text_color_left = metal_visuals[metal_names[j]]["text_color_left"],
-                text_color_right = metal_visuals[metal_names[j]]["text_color_right"],
-                --back_color_left = metal_visuals[metal_names[j]]["back_color_left"],
-                --back_color_right = metal_visuals[metal_names[j]]["back_color_right"]
-            }
-
-            Core.InitializationInfo.GameData[init.conveyorLineCoresIndex][identifier] = {
-                item = item,
-                visual_data = visual_data
+            gameData[identifier] = {
+                extends = init.source..":"..material_types[i],
+                visual_data = metal_visuals[metal_names[j]]
             }
 
         end
@@ -1,7 +1,290 @@
+use std::collections::BTreeMap;
 
+use once_cell::sync::Lazy;
 use rlua::{Context, Function, Table, Value};
 
-use crate::{lua, write_to_debug_pretty};
+use crate::{identifier_dump, lua};
+
+pub static mut COMMAND_REGISTRY: Lazy<BTreeMap<String, CommandInfo>> = Lazy::new(BTreeMap::new);
+/// safe unsafe action lolz
+// commands are only registered and run on the main thread
+#[allow(static_mut_refs)]
+pub fn command_registry() -> &'static mut BTreeMap<String, CommandInfo> { unsafe { &mut COMMAND_REGISTRY } }
+
+/// What kind of value a command argument has to be
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CommandArgType {
+    String,
+    Number,
+    Table,
+    /// a registered tile identifier, e.g. "conveyor_line_inc:rock"
+    Tile,
+    Any,
+}
+
+impl CommandArgType {
+    fn from_str(arg_type: &str) -> Option<Self> {
+        match arg_type {
+            "string" => Some(Self::String),
+            "number" => Some(Self::Number),
+            "table" => Some(Self::Table),
+            "tile" => Some(Self::Tile),
+            "any" => Some(Self::Any),
+            _ => None,
+        }
+    }
+}
+
+/// A single argument as declared in Core.Commands.register
+#[derive(Clone, Debug)]
+pub struct CommandArg {
+    pub name: String,
+    pub arg_type: CommandArgType,
+    pub optional: bool,
+    /// extra values offered by tab completion
+    pub completions: Vec<String>,
+}
+
+/// Everything known about a registered command except its handler, which lives in the lua registry
+#[derive(Clone, Debug)]
+pub struct CommandInfo {
+    pub name: String,
+    pub usage: String,
+    pub description: String,
+    pub args: Vec<CommandArg>,
+    pub aliases: Vec<String>,
+}
+
+fn handler_registry_key(name: &str) -> String {
+    format!("command_handler:{}", name)
+}
+
+/// finds a command by its name or one of its aliases
+pub fn find_command(name: &str) -> Option<&'static CommandInfo> {
+    let registry = command_registry();
+    match registry.get(name) {
+        Some(info) => Some(info),
+        None => registry.values().find(|info| info.aliases.iter().any(|alias| alias == name)),
+    }
+}
+
+/// registers a command from the spec table given to Core.Commands.register
+pub fn register_command<'lua>(lua_context: Context<'lua>, name: String, spec: Table<'lua>) -> Result<(), String> {
+    let handler = spec.get::<_, Function>("handler").map_err(|_| format!("command '{}' has no handler function", name))?;
+
+    let mut args = Vec::new();
+    if let Ok(arg_specs) = spec.get::<_, Table>("args") {
+        for arg_spec in arg_specs.sequence_values::<Table>() {
+            let arg_spec = arg_spec.map_err(|e| format!("command '{}' has a malformed argument: {}", name, e))?;
+
+            let arg_name: String = arg_spec.get("name").unwrap_or_else(|_| format!("arg{}", args.len() + 1));
+            let arg_type = match arg_spec.get::<_, Option<String>>("type").unwrap_or(None) {
+                Some(arg_type) => CommandArgType::from_str(&arg_type)
+                    .ok_or_else(|| format!("command '{}' argument <{}> has unknown type '{}'", name, arg_name, arg_type))?,
+                None => CommandArgType::Any,
+            };
+            let completions = match arg_spec.get::<_, Table>("completions") {
+                Ok(completions) => completions.sequence_values::<String>().filter_map(Result::ok).collect(),
+                Err(_) => Vec::new(),
+            };
+
+            args.push(CommandArg {
+                name: arg_name,
+                arg_type,
+                optional: arg_spec.get("optional").unwrap_or(false),
+                completions,
+            });
+        }
+    }
+
+    let usage = match spec.get::<_, String>("usage") {
+        Ok(usage) => usage,
+        Err(_) => generated_usage(&name, &args),
+    };
+
+    let aliases = match spec.get::<_, Table>("aliases") {
+        Ok(aliases) => aliases.sequence_values::<String>().filter_map(Result::ok).collect(),
+        Err(_) => Vec::new(),
+    };
+
+    lua_context.set_named_registry_value(&handler_registry_key(&name), handler)
+        .map_err(|e| format!("failed to store handler of '{}': {}", name, e))?;
+
+    command_registry().insert(name.clone(), CommandInfo {
+        name,
+        usage,
+        description: spec.get("description").unwrap_or_default(),
+        args,
+        aliases,
+    });
+
+    Ok(())
+}
+
+fn generated_usage(name: &str, args: &[CommandArg]) -> String {
+    let mut usage = String::from(name);
+    for arg in args {
+        if arg.optional {
+            usage.push_str(&format!(" [{}]", arg.name));
+        } else {
+            usage.push_str(&format!(" <{}>", arg.name));
+        }
+    }
+    usage
+}
+
+/// checks the arguments of a split input against the command's declared arguments
+fn validate_arguments(info: &CommandInfo, arguments: Option<&Table>) -> Result<(), String> {
+    for (i, arg) in info.args.iter().enumerate() {
+        let value = match arguments {
+            Some(arguments) => arguments.get::<_, Value>(i + 1).unwrap_or(Value::Nil),
+            None => Value::Nil,
+        };
+
+        let fail = |reason: String| Err(format!("{}: {}\nusage: {}", info.name, reason, info.usage));
+
+        match (&value, arg.arg_type) {
+            (Value::Nil, _) if arg.optional => {},
+            (Value::Nil, _) => return fail(format!("missing argument <{}>", arg.name)),
+            (_, CommandArgType::Any) => {},
+            (Value::Table(_), CommandArgType::Table) => {},
+            (_, CommandArgType::Table) => return fail(format!("<{}> must be a {{table}}", arg.name)),
+            (Value::Table(_), _) => return fail(format!("<{}> can't be a table", arg.name)),
+            (Value::Integer(_) | Value::Number(_), CommandArgType::Number) => {},
+            (Value::String(s), CommandArgType::Number) if s.to_str().unwrap_or("").parse::<f64>().is_err() => {
+                return fail(format!("<{}> must be a number, got '{}'", arg.name, s.to_str().unwrap_or("invalid utf-8")));
+            },
+            (Value::String(_), CommandArgType::Number) => {},
+            (Value::String(s), CommandArgType::Tile) => {
+                let s = s.to_str().unwrap_or("");
+                if identifier_dump().tile_types.get_by_left(s).is_none() {
+                    return fail(format!("'{}' isn't a tile type", s));
+                }
+            },
+            _ => {},
+        }
+    }
+
+    Ok(())
+}
+
+/// runs a registered command with a split input, returning the handler's output or a readable error
+pub fn run_command<'lua>(lua_context: Context<'lua>, name: &str, input: Table<'lua>) -> Value<'lua> {
+    let info = match find_command(name) {
+        Some(info) => info,
+        None => return Value::String(lua_context.create_string(&format!("unknown command '{}', try help", name)).unwrap()),
+    };
+
+    let arguments = input.get::<_, Table>("arguments").ok();
+    if let Err(e) = validate_arguments(info, arguments.as_ref()) {
+        return Value::String(lua_context.create_string(&e).unwrap());
+    }
+
+    let handler = match lua_context.named_registry_value::<_, Function>(&handler_registry_key(&info.name)) {
+        Ok(handler) => handler,
+        Err(_) => return Value::Nil,
+    };
+    match handler.call::<_, Value>(input) {
+        Ok(output) => output,
+        Err(e) => {
+            log_error!("{}:\n{}", info.name, e);
+            Value::String(lua_context.create_string(&format!("{} failed, see debug log", info.name)).unwrap())
+        },
+    }
+}
+
+/// help text for all commands, or the usage and description of one
+pub fn command_help(name: Option<&str>) -> String {
+    match name {
+        Some(name) => match find_command(name) {
+            Some(info) => {
+                let mut help = format!("usage: {}", info.usage);
+                if !info.description.is_empty() {
+                    help.push_str(&format!("\n{}", info.description));
+                }
+                if !info.aliases.is_empty() {
+                    help.push_str(&format!("\naliases: {}", info.aliases.join(", ")));
+                }
+                help
+            },
+            None => format!("unknown command '{}'", name),
+        },
+        None => {
+            let names: Vec<&str> = command_registry().keys().map(|name| name.as_str()).collect();
+            format!("commands: {}\nhelp <command> for more", names.join(", "))
+        },
+    }
+}
+
+/// tab completion for the text behind the cursor
+/// returns the completed text, or None if there is nothing to complete
+pub fn complete_input(text: &str) -> Option<String> {
+    let (completed, current) = match text.rfind(' ') {
+        Some(i) => text.split_at(i + 1),
+        None => ("", text),
+    };
+
+    let mut words = completed.split_whitespace();
+
+    // nothing but whitespace before the cursor means the command name is being typed
+    let candidates: Vec<String> = match words.clone().count().checked_sub(1) {
+        None => command_registry().values()
+            .flat_map(|info| std::iter::once(info.name.clone()).chain(info.aliases.iter().cloned()))
+            .collect(),
+        Some(arg_index) => match words.next().and_then(find_command) {
+            Some(info) => match info.args.get(arg_index) {
+                Some(arg) => {
+                    let mut candidates = arg.completions.clone();
+                    if arg.arg_type == CommandArgType::Tile {
+                        candidates.extend(identifier_dump().tile_types.left_values().cloned());
+                    }
+                    candidates
+                },
+                None => Vec::new(),
+            },
+            None => Vec::new(),
+        },
+    };
+
+    if candidates.iter().any(|c| c.starts_with(current)) {
+        return complete_from_candidates(completed, current, &candidates);
+    }
+
+    // identifiers can still be completed when the argument doesn't declare them
+    if current.contains(':') {
+        let idents = identifier_dump();
+        let identifiers: Vec<String> = idents.tile_types.left_values()
+            .chain(idents.ground_types.left_values())
+            .chain(idents.item_types.left_values())
+            .cloned()
+            .collect();
+        return complete_from_candidates(completed, current, &identifiers);
+    }
+
+    None
+}
+
+/// completes to the only matching candidate, or as far as all matching candidates agree
+fn complete_from_candidates(completed: &str, current: &str, candidates: &[String]) -> Option<String> {
+    let mut candidates: Vec<&String> = candidates.iter().filter(|c| c.starts_with(current)).collect();
+    candidates.sort();
+    candidates.dedup();
+
+    match candidates.as_slice() {
+        [] => None,
+        [only] => Some(format!("{}{} ", completed, only)),
+        [first, rest @ ..] => {
+            let common = rest.iter().fold(first.len(), |common, c| {
+                first.chars().zip(c.chars()).take_while(|(a, b)| a == b).map(|(a, _)| a.len_utf8()).sum::<usize>().min(common)
+            });
+            if common > current.len() {
+                Some(format!("{}{}", completed, &first[..common]))
+            } else {
+                None
+            }
+        },
+    }
+}
 
 pub fn action_from_input(input: &str) {
     lua().lock().unwrap().context(|lua_context| {
@@ -19,10 +302,10 @@ pub fn action_from_input_with_context(lua_context: Context, input: &str) {
                 for pair in events_table.pairs::<Value, Function>() {
                     let pair = pair.unwrap();
                     if let Err(e) = pair.1.call::<&str, ()>(input) {
-                        write_to_debug_pretty(format!("{:?}:\n{:?}", pair.0, e));
+                        log_error!("{:?}:\n{}", pair.0, e);
                     }
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -1,19 +1,197 @@
-use std::{fmt::{Debug, Display}, fs};
+use std::{collections::HashMap, fmt::Display, fs::{self, File, OpenOptions}, io::Write, path::{Path, PathBuf}};
 
-pub fn write_to_debug<T: Display + Debug>(text: T) {
-    let mut data = fs::read_to_string("src/tmp/debug.txt").expect("Unable to read file");
-    data = format!("{}{}\n", data, text);
-    fs::write("src/tmp/debug.txt", data).expect("Unable to write file");
+use json::JsonValue;
+use once_cell::sync::Lazy;
+
+use crate::tick_count;
+
+pub const CONFIG_PATH: &str = r"resources/config/config.json";
+pub const DEFAULT_LOG_PATH: &str = r"logs/conveyor_line_inc.log";
+
+/// How important a log entry or message is
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Severity {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Severity {
+    pub fn from_str(severity: &str) -> Option<Self> {
+        match severity.to_lowercase().as_str() {
+            "debug" => Some(Self::Debug),
+            "info" => Some(Self::Info),
+            "warn" | "warning" => Some(Self::Warn),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// Where and how much gets logged, read from "log" in conveyor_line_engine_config
+#[derive(Clone, Debug)]
+pub struct LoggerConfig {
+    pub path: PathBuf,
+    /// the log file is rotated once it would grow past this many bytes
+    pub max_file_size: u64,
+    /// how many rotated files (path.1, path.2, ...) are kept
+    pub max_rotated_files: usize,
+    pub level: Severity,
+    /// levels for specific targets, the longest matching target prefix wins
+    pub target_levels: HashMap<String, Severity>,
+}
+
+impl Default for LoggerConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from(DEFAULT_LOG_PATH),
+            max_file_size: 1024 * 1024,
+            max_rotated_files: 3,
+            level: Severity::Info,
+            target_levels: HashMap::new(),
+        }
+    }
+}
+
+impl LoggerConfig {
+    /// missing or malformed fields keep their defaults
+    pub fn from_json(log_config: &JsonValue) -> Self {
+        let mut config = Self::default();
+
+        if let Some(path) = log_config["path"].as_str() {
+            config.path = PathBuf::from(path);
+        }
+        if let Some(max_file_size) = log_config["max_file_size"].as_u64() {
+            config.max_file_size = max_file_size;
+        }
+        if let Some(max_rotated_files) = log_config["max_rotated_files"].as_usize() {
+            config.max_rotated_files = max_rotated_files;
+        }
+        if let Some(level) = log_config["level"].as_str().and_then(Severity::from_str) {
+            config.level = level;
+        }
+        for (target, level) in log_config["targets"].entries() {
+            if let Some(level) = level.as_str().and_then(Severity::from_str) {
+                config.target_levels.insert(target.to_owned(), level);
+            }
+        }
+
+        config
+    }
+}
+
+/// Append only file logger with size based rotation
+pub struct Logger {
+    config: LoggerConfig,
+    file: Option<File>,
+    file_size: u64,
+}
+
+pub static mut LOGGER: Lazy<Logger> = Lazy::new(|| Logger::new(LoggerConfig::default()));
+/// safe unsafe action lolz
+// only logged to from the main thread
+#[allow(static_mut_refs)]
+pub fn logger() -> &'static mut Logger { unsafe { &mut LOGGER } }
+
+impl Logger {
+    pub fn new(config: LoggerConfig) -> Self {
+        Self {
+            config,
+            file: None,
+            file_size: 0,
+        }
+    }
+
+    pub fn configure(&mut self, config: LoggerConfig) {
+        self.config = config;
+        self.file = None;
+    }
+
+    pub fn enabled(&self, level: Severity, target: &str) -> bool {
+        let min_level = self.config.target_levels.iter()
+            .filter(|(prefix, _)| target.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.config.level);
+
+        level >= min_level
+    }
+
+    pub fn write(&mut self, level: Severity, target: &str, text: &str) {
+        if !self.enabled(level, target) { return; }
+
+        let entry = format!("[tick {}] {} {}: {}\n", tick_count(), level.label().to_uppercase(), target, text);
+
+        // opened first, so a log which was already too big before starting is rotated too
+        if self.open().is_some() && self.file_size > 0 && self.file_size + entry.len() as u64 > self.config.max_file_size {
+            self.rotate();
+        }
+
+        if let Some(file) = self.open() {
+            if file.write_all(entry.as_bytes()).is_ok() {
+                self.file_size += entry.len() as u64;
+            }
+        }
+    }
+
+    /// opens the log file for appending, creating its directory if needed
+    fn open(&mut self) -> Option<&mut File> {
+        if self.file.is_none() {
+            if let Some(parent) = self.config.path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let file = OpenOptions::new().create(true).append(true).open(&self.config.path).ok()?;
+            self.file_size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            self.file = Some(file);
+        }
+        self.file.as_mut()
+    }
+
+    /// path -> path.1 -> path.2 ..., dropping the oldest
+    fn rotate(&mut self) {
+        self.file = None;
+
+        let path = &self.config.path;
+        if self.config.max_rotated_files == 0 {
+            let _ = fs::remove_file(path);
+            return;
+        }
+
+        let _ = fs::remove_file(rotated_path(path, self.config.max_rotated_files));
+        for i in (1..self.config.max_rotated_files).rev() {
+            let _ = fs::rename(rotated_path(path, i), rotated_path(path, i + 1));
+        }
+        let _ = fs::rename(path, rotated_path(path, 1));
+    }
 }
 
-pub fn write_to_debug_pretty(text: String) {
-    write_to_debug(text.replace("{", "{\n")
-        .replace("}", "}\n")
-        .replace(r"\n", "\n")
-        .replace(r"\t", "\t")
-    );
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", index));
+    PathBuf::from(rotated)
 }
 
-pub fn clear_debug() {
-    fs::write("src/tmp/debug.txt", "").expect("Unable to write file");
-}
\ No newline at end of file
+/// configures the logger from the engine config, falling back to the defaults
+pub fn init_logging() {
+    let config = fs::read_to_string(CONFIG_PATH).ok()
+        .and_then(|config| json::parse(&config).ok())
+        .map(|config| LoggerConfig::from_json(&config["conveyor_line_engine_config"]["log"]))
+        .unwrap_or_default();
+
+    logger().configure(config);
+}
+
+/// prefer the log_* macros, which fill in the target with the calling module
+pub fn write_log<T: Display>(level: Severity, target: &str, text: T) {
+    logger().write(level, target, &text.to_string());
+}
@@ -8,7 +8,7 @@ pub fn color_format_char(text_color: String, back_color: String, char: char) ->
 
 auto_builder! {
     /// 2 characters which represents how to display something
-    #[derive(Clone, Copy, Debug)]
+    #[derive(Clone, Copy, Debug, PartialEq)]
     pub TextDisplay
     pub TextDisplayBuilder
     pub character_left: Option<char>,
@@ -17,7 +17,7 @@ auto_builder! {
 
 auto_builder! {
     /// 2 colors which represent how to display something
-    #[derive(Clone, Copy, Debug)]
+    #[derive(Clone, Copy, Debug, PartialEq)]
     pub ColorDisplay
     pub ColorDisplayBuilder
     pub text_color_left: Option<(u8, u8, u8)>,
@@ -89,6 +89,28 @@ impl ColorDisplayBuilder {
     }
 }
 
+/// Frames which are shown one after another, each for frame_duration ticks
+/// things which don't animate have a single frame
+#[derive(Clone, Debug)]
+pub struct Animation {
+    pub frames: Vec<(TextDisplay, ColorDisplay)>,
+    pub frame_duration: u64,
+}
+
+impl Animation {
+    pub fn still(text_display: TextDisplay, color_display: ColorDisplay) -> Self {
+        Self {
+            frames: vec![(text_display, color_display)],
+            frame_duration: 1,
+        }
+    }
+
+    /// the frame shown at tick, every animation of the same type is in step
+    pub fn frame_at(&self, tick: u64) -> (TextDisplay, ColorDisplay) {
+        self.frames[((tick / self.frame_duration) % self.frames.len() as u64) as usize]
+    }
+}
+
 /// Returns the ansi sequence for a struct with a DisplayInfo field
 pub trait HasTextColor {
     fn ansi_text_colors (&self) -> ColorDisplay;
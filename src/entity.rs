@@ -0,0 +1,129 @@
+use std::collections::{BTreeMap, HashMap};
+
+use once_cell::sync::Lazy;
+
+use crate::{display::{ColorDisplay, HasBackColor, HasTextColor, HasTextDisplay, TextDisplay}, game_data_dump, on_map};
+
+pub static mut ENTITIES: once_cell::sync::Lazy<Entities> = Lazy::<Entities>::new(Entities::new);
+/// safe unsafe action lolz
+// entities are only changed by ticks and scripts, which both run on the main thread
+#[allow(static_mut_refs)]
+pub fn entities() -> &'static mut Entities { unsafe { &mut ENTITIES } }
+
+/// Something which moves freely over the map, drawn above tiles and below the player
+/// its lua state lives in the lua registry, see Core.Entities
+#[derive(Clone, Copy, Debug)]
+pub struct Entity {
+    pub id: u32,
+    pub entity_type: u16,
+    pub position: (usize, usize),
+    pub text_display: TextDisplay,
+    pub color_display: ColorDisplay,
+}
+
+/// The types of entities an Entity can represent
+#[derive(Debug)]
+pub struct EntityType {
+    pub identifier: u16,
+    pub text_display: TextDisplay,
+    pub color_display: ColorDisplay,
+}
+
+/// Every entity on the map by id, ids are never reused
+pub struct Entities {
+    next_id: u32,
+    entities: BTreeMap<u32, Entity>,
+}
+
+impl Entities {
+    pub fn new() -> Self {
+        Self {
+            next_id: 0,
+            entities: BTreeMap::new(),
+        }
+    }
+
+    /// None if the entity type doesn't exist or the position is off the map
+    pub fn spawn(&mut self, entity_type: u16, position: (usize, usize)) -> Option<u32> {
+        if !on_map(position) { return None; }
+        let entity_type_data = game_data_dump().entity_types.get(&entity_type)?;
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entities.insert(id, Entity {
+            id,
+            entity_type,
+            position,
+            text_display: entity_type_data.text_display,
+            color_display: entity_type_data.color_display,
+        });
+
+        Some(id)
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Entity> {
+        self.entities.get(&id)
+    }
+
+    pub fn remove(&mut self, id: u32) -> Option<Entity> {
+        self.entities.remove(&id)
+    }
+
+    /// false if the entity doesn't exist or the position is off the map
+    pub fn set_position(&mut self, id: u32, position: (usize, usize)) -> bool {
+        match self.entities.get_mut(&id) {
+            Some(entity) if on_map(position) => {
+                entity.position = position;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// removes every entity, ids still aren't reused
+    pub fn clear(&mut self) {
+        self.entities.clear();
+    }
+
+    /// the first entity at position, if any
+    pub fn at(&self, position: (usize, usize)) -> Option<&Entity> {
+        self.entities.values().find(|entity| entity.position == position)
+    }
+
+    /// every id in order, a copy so entities can be added and removed while going through them
+    pub fn ids(&self) -> Vec<u32> {
+        self.entities.keys().copied().collect()
+    }
+
+    /// entities inside the rectangle with its top left corner at top_left, ordered by id
+    pub fn in_rect(&self, top_left: (usize, usize), size: (usize, usize)) -> impl Iterator<Item = &Entity> {
+        self.entities.values().filter(move |entity| {
+            (top_left.0..top_left.0.saturating_add(size.0)).contains(&entity.position.0)
+                && (top_left.1..top_left.1.saturating_add(size.1)).contains(&entity.position.1)
+        })
+    }
+
+    /// the entity which is drawn at each position, the most recently spawned one wins
+    pub fn by_position(&self) -> HashMap<(usize, usize), &Entity> {
+        self.entities.values().map(|entity| (entity.position, entity)).collect()
+    }
+}
+
+// HasColor implements
+impl HasTextColor for Entity {
+    fn ansi_text_colors (&self) -> ColorDisplay {
+        self.color_display
+    }
+}
+
+impl HasBackColor for Entity {
+    fn ansi_back_colors (&self) -> ColorDisplay {
+        self.color_display
+    }
+}
+
+impl HasTextDisplay for Entity {
+    fn text_display (&self) -> TextDisplay {
+        self.text_display
+    }
+}
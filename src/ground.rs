@@ -9,7 +9,7 @@ pub struct Ground {
 }
 
 impl Ground {
-    pub fn new(ground_type: u16) -> Self {
+    pub fn new_unchecked(ground_type: u16) -> Self {
         let tile_from_map = game_data_dump().ground_types.get(&ground_type).unwrap();
 
         Self {
@@ -18,12 +18,22 @@ impl Ground {
             color_display: tile_from_map.color_display
         }
     }
+    pub fn new(ground_type: u16) -> Option<Self> {
+        game_data_dump().ground_types.get(&ground_type).map(|ground_from_map| {
+            Self {
+                ground_type,
+                text_display: ground_from_map.text_display,
+                color_display: ground_from_map.color_display
+            }
+        })
+    }
 }
 
 /// The types of ground that Ground can represent
 #[derive(Debug)]
 pub struct GroundType {
     pub identifier: u16,
+    pub name: String,
     pub text_display: TextDisplay,
     pub color_display: ColorDisplay,
     pub solid: bool,
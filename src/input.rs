@@ -1,131 +1,564 @@
+use std::{fs, path::{Path, PathBuf}};
 
 use crossterm::{cursor, event::{KeyCode, KeyEvent, KeyModifiers}, QueueableCommand};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-use crate::{map_render::MAP_HEIGHT, print_input_text, std_out, wipe_input};
+use crate::{complete_input, print_input_text, std_out, wipe_input};
 
 pub const MAX_INPUT_CHARS: usize = 35;
-pub static mut BEHIND_PRINT_AMOUNT: usize = MAX_INPUT_CHARS;
-
-/// safe unsafe action lolz
-pub fn behind_print_amount() ->  usize { unsafe { BEHIND_PRINT_AMOUNT } }
-
-/// not that much to type, but enough for a function
-/// safe unsafe action lolz
-pub fn increment_behind_print_amount(incrementation: usize, is_decrement: bool) {
-    unsafe { 
-        BEHIND_PRINT_AMOUNT = if is_decrement {
-            if incrementation <= BEHIND_PRINT_AMOUNT {
-                BEHIND_PRINT_AMOUNT - incrementation
-            } else {
-                0
-            }
-        } else {
-            (BEHIND_PRINT_AMOUNT + incrementation).min(MAX_INPUT_CHARS)
-        };
-    };
+
+pub const COMMAND_HISTORY_PATH: &str = r"resources/config/command_history.txt";
+/// how many submitted lines are kept in memory and loaded on startup
+pub const COMMAND_HISTORY_CAP: usize = 500;
+
+/// State of an in progress Ctrl+R search through the history
+struct HistorySearch {
+    query: String,
+    /// how many older matches to skip, incremented by pressing Ctrl+R again
+    skip: usize,
 }
 
-/// handles what happens in accordance to input key event
-/// returns an input when enter is pressed
-pub fn key_output(event: KeyEvent, chars_behind_cursor: &mut Vec<char>, chars_ahead_cursor: &mut Vec<char>) -> Option<String> {
-    let _ = std_out().queue(cursor::RestorePosition);
-    let mut current_input = None;
-
-    match event.code {
-        // normal typing
-        KeyCode::Char(c) => {
-            chars_behind_cursor.push(c);
-
-            increment_behind_print_amount(1, false);
-
-            print_input_text(chars_behind_cursor, chars_ahead_cursor);
-        },
-        // normal backspace funtionality
-        KeyCode::Backspace if event.modifiers == KeyModifiers::CONTROL => {
-            let looking_for_space = chars_behind_cursor.last() == Some(&' ');
-            if looking_for_space {
-                loop {
-                    if chars_behind_cursor.last() != Some(&' ') || chars_behind_cursor.is_empty() { break; }
-                    chars_behind_cursor.pop();
+/// The text in the input line along with its history
+/// cursor is a byte offset which always sits on a grapheme boundary
+pub struct InputLine {
+    text: String,
+    cursor: usize,
+    /// index of the first visible grapheme
+    scroll: usize,
+    history: Vec<String>,
+    /// where submitted lines are saved
+    history_path: PathBuf,
+    /// Some(_) while browsing the history with Up/Down
+    history_index: Option<usize>,
+    /// what was typed before browsing the history
+    draft: String,
+    search: Option<HistorySearch>,
+}
+
+impl InputLine {
+    pub fn new() -> Self {
+        Self::with_history_file(COMMAND_HISTORY_PATH)
+    }
+
+    fn with_history_file(history_path: impl Into<PathBuf>) -> Self {
+        let history_path = history_path.into();
+        let history = match fs::read_to_string(&history_path) {
+            Ok(history) => {
+                let lines: Vec<String> = history.lines().filter(|line| !line.is_empty()).map(String::from).collect();
+                lines[lines.len().saturating_sub(COMMAND_HISTORY_CAP)..].to_vec()
+            },
+            Err(_) => Vec::new(),
+        };
+
+        Self {
+            text: String::new(),
+            cursor: 0,
+            scroll: 0,
+            history,
+            history_path,
+            history_index: None,
+            draft: String::new(),
+            search: None,
+        }
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    pub fn text_before_cursor(&self) -> &str {
+        &self.text[..self.cursor]
+    }
+
+    /// used by tab completion, keeps the text after the cursor intact
+    pub fn replace_text_before_cursor(&mut self, replacement: &str) {
+        self.text.replace_range(..self.cursor, replacement);
+        self.cursor = replacement.len();
+    }
+
+    /// the grapheme the cursor is on top of, if any
+    pub fn grapheme_at_cursor(&self) -> Option<&str> {
+        self.text[self.cursor..].graphemes(true).next()
+    }
+
+    /// the text as it should be shown, and the cursor's byte offset in it
+    fn display_text(&self) -> (String, usize) {
+        match &self.search {
+            Some(search) => {
+                let prefix = format!("search '{}': ", search.query);
+                let cursor = prefix.len() - 3;
+                (format!("{}{}", prefix, self.search_match().unwrap_or("")), cursor)
+            },
+            None => (self.text.clone(), self.cursor),
+        }
+    }
+
+    /// returns the visible part of the line, the column of the cursor inside it,
+    /// and if text is cut off on the left and right
+    pub fn visible_text(&mut self, width: usize) -> (String, usize, bool, bool) {
+        let (text, cursor) = self.display_text();
+        let graphemes: Vec<(usize, &str)> = text.grapheme_indices(true).collect();
+        let cursor_index = graphemes.iter().position(|(i, _)| *i >= cursor).unwrap_or(graphemes.len());
+
+        // keep the cursor in view, with space for it at the end of the line
+        if cursor_index < self.scroll {
+            self.scroll = cursor_index;
+        }
+        while self.scroll < cursor_index && width_of(&graphemes[self.scroll..cursor_index]) >= width {
+            self.scroll += 1;
+        }
+
+        let mut visible = String::new();
+        let mut used_width = 0;
+        let mut last_shown = self.scroll;
+        for (_, grapheme) in &graphemes[self.scroll..] {
+            let grapheme_width = grapheme.width();
+            if used_width + grapheme_width > width { break; }
+
+            visible.push_str(grapheme);
+            used_width += grapheme_width;
+            last_shown += 1;
+        }
+
+        let cursor_column = width_of(&graphemes[self.scroll..cursor_index]);
+        (visible, cursor_column, self.scroll > 0, last_shown < graphemes.len())
+    }
+
+    fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+        // combining characters join the grapheme before them
+        self.cursor = self.grapheme_boundary_at_or_after(self.cursor);
+    }
+
+    fn grapheme_boundary_at_or_after(&self, offset: usize) -> usize {
+        self.text.grapheme_indices(true)
+            .map(|(i, _)| i)
+            .find(|i| *i >= offset)
+            .unwrap_or(self.text.len())
+    }
+
+    fn previous_boundary(&self) -> Option<usize> {
+        self.text[..self.cursor].grapheme_indices(true).next_back().map(|(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> Option<usize> {
+        self.text[self.cursor..].graphemes(true).next().map(|g| self.cursor + g.len())
+    }
+
+    /// start of the word behind the cursor, skipping spaces first
+    fn previous_word_boundary(&self) -> usize {
+        let before = &self.text[..self.cursor];
+        let trimmed = before.trim_end_matches(' ');
+        match trimmed.rfind(' ') {
+            Some(i) => i + 1,
+            None => 0,
+        }
+    }
+
+    /// end of the word ahead of the cursor, skipping spaces first
+    fn next_word_boundary(&self) -> usize {
+        let after = &self.text[self.cursor..];
+        let skipped = after.len() - after.trim_start_matches(' ').len();
+        match after[skipped..].find(' ') {
+            Some(i) => self.cursor + skipped + i,
+            None => self.text.len(),
+        }
+    }
+
+    fn set_text(&mut self, text: String) {
+        self.cursor = text.len();
+        self.text = text;
+    }
+
+    fn history_up(&mut self) {
+        if self.history.is_empty() { return; }
+
+        let index = match self.history_index {
+            Some(index) => index.saturating_sub(1),
+            None => {
+                self.draft = self.text.clone();
+                self.history.len() - 1
+            },
+        };
+        self.history_index = Some(index);
+        self.set_text(self.history[index].clone());
+    }
+
+    fn history_down(&mut self) {
+        match self.history_index {
+            Some(index) if index + 1 < self.history.len() => {
+                self.history_index = Some(index + 1);
+                self.set_text(self.history[index + 1].clone());
+            },
+            Some(_) => {
+                self.history_index = None;
+                self.set_text(self.draft.clone());
+            },
+            None => {},
+        }
+    }
+
+    fn search_match(&self) -> Option<&str> {
+        let search = self.search.as_ref()?;
+        self.history.iter().rev()
+            .filter(|line| line.contains(&search.query))
+            .nth(search.skip)
+            .map(|line| line.as_str())
+    }
+
+    /// leaves search mode, putting the found line into the input
+    fn accept_search(&mut self) {
+        if let Some(found) = self.search_match().map(String::from) {
+            self.set_text(found);
+        }
+        self.search = None;
+    }
+
+    /// takes the current text out of the line and adds it to the history
+    fn submit(&mut self) -> String {
+        let submitted = std::mem::take(&mut self.text);
+        self.cursor = 0;
+        self.scroll = 0;
+        self.history_index = None;
+        self.draft.clear();
+
+        if !submitted.trim().is_empty() && self.history.last() != Some(&submitted) {
+            self.history.push(submitted.clone());
+            if self.history.len() > COMMAND_HISTORY_CAP {
+                self.history.remove(0);
+            }
+            save_history_file(&self.history_path, &self.history);
+        }
+
+        submitted
+    }
+
+    /// changes the line for a key event without drawing anything
+    /// returns the submitted text when enter is pressed
+    fn apply_key(&mut self, event: KeyEvent) -> Option<String> {
+        let control = event.modifiers.contains(KeyModifiers::CONTROL);
+
+        if self.is_searching() {
+            self.apply_search_key(event);
+            return None;
+        }
+
+        match event.code {
+            // history search
+            KeyCode::Char('r') if control => {
+                self.search = Some(HistorySearch { query: String::new(), skip: 0 });
+            },
+            // other control combinations shouldn't type anything
+            KeyCode::Char(_) if control => {},
+            // normal typing
+            KeyCode::Char(c) => {
+                self.insert(c);
+            },
+            // normal backspace funtionality
+            KeyCode::Backspace if control => {
+                let start = self.previous_word_boundary();
+                self.text.replace_range(start..self.cursor, "");
+                self.cursor = start;
+            },
+            KeyCode::Backspace => {
+                if let Some(start) = self.previous_boundary() {
+                    self.text.replace_range(start..self.cursor, "");
+                    self.cursor = start;
                 }
-            } else {
-                loop {
-                    if chars_behind_cursor.last() == Some(&' ') || chars_behind_cursor.is_empty() { break; }
-                    chars_behind_cursor.pop();
+            },
+            // normal delete functionality
+            KeyCode::Delete if control => {
+                let end = self.next_word_boundary();
+                self.text.replace_range(self.cursor..end, "");
+            },
+            KeyCode::Delete => {
+                if let Some(end) = self.next_boundary() {
+                    self.text.replace_range(self.cursor..end, "");
                 }
-            }
-            print_input_text(chars_behind_cursor, chars_ahead_cursor);
-        },
-        KeyCode::Backspace => {
-            chars_behind_cursor.pop();
-
-            print_input_text(chars_behind_cursor, chars_ahead_cursor);
-        },
-        // normal delete functionality
-        KeyCode::Delete if event.modifiers == KeyModifiers::CONTROL => {
-            let looking_for_space = chars_ahead_cursor.last() == Some(&' ');
-            if looking_for_space {
-                loop {
-                    if chars_ahead_cursor.last() != Some(&' ') || chars_ahead_cursor.is_empty() { break; }
-                    chars_ahead_cursor.pop();
+            },
+            // navigation keys
+            KeyCode::Left if control => {
+                self.cursor = self.previous_word_boundary();
+            },
+            KeyCode::Left => {
+                if let Some(previous) = self.previous_boundary() {
+                    self.cursor = previous;
                 }
-            } else {
-                loop {
-                    if chars_ahead_cursor.last() == Some(&' ') || chars_ahead_cursor.is_empty() { break; }
-                    chars_ahead_cursor.pop();
+            },
+            KeyCode::Right if control => {
+                self.cursor = self.next_word_boundary();
+            },
+            KeyCode::Right => {
+                if let Some(next) = self.next_boundary() {
+                    self.cursor = next;
                 }
-            }
-            print_input_text(chars_behind_cursor, chars_ahead_cursor);
-        },
-        KeyCode::Delete => {
-            chars_ahead_cursor.pop();
-
-            print_input_text(chars_behind_cursor, chars_ahead_cursor);
-        },
-        // navigation keys
-        KeyCode::Left => {
-            match chars_behind_cursor.pop() {
-                Some(c) => {
-                    chars_ahead_cursor.push(c);
-
-                    increment_behind_print_amount(1, true);
-
-                    print_input_text(chars_behind_cursor, chars_ahead_cursor);
-                },
-                _ => {},
-            }
-        },
-        KeyCode::Right => {
-            match chars_ahead_cursor.pop() {
-                Some(c) => {
-                    chars_behind_cursor.push(c);
+            },
+            KeyCode::Home => {
+                self.cursor = 0;
+            },
+            KeyCode::End => {
+                self.cursor = self.text.len();
+            },
+            // history
+            KeyCode::Up => {
+                self.history_up();
+            },
+            KeyCode::Down => {
+                self.history_down();
+            },
+            // submit command
+            KeyCode::Enter => {
+                return Some(self.submit());
+            },
+            _ => {}
+        };
 
-                    increment_behind_print_amount(1, false);
+        None
+    }
 
-                    print_input_text(chars_behind_cursor, chars_ahead_cursor);
-                },
-                _ => {},
-            }
-        },
-        // submit command
-        KeyCode::Enter => {
-            ignorant_queue!(std_out, cursor::MoveTo(27, MAP_HEIGHT as u16 + 6));
-            ignorant_execute!(std_out, cursor::SavePosition);
+    /// handles key events while searching the history with Ctrl+R
+    fn apply_search_key(&mut self, event: KeyEvent) {
+        let search = self.search.as_mut().unwrap();
+
+        match event.code {
+            KeyCode::Char('r') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                search.skip += 1;
+                if self.search_match().is_none() {
+                    self.search.as_mut().unwrap().skip -= 1;
+                }
+            },
+            KeyCode::Char(c) if !event.modifiers.contains(KeyModifiers::CONTROL) => {
+                search.query.push(c);
+                search.skip = 0;
+            },
+            KeyCode::Backspace => {
+                search.query.pop();
+                search.skip = 0;
+            },
+            KeyCode::Esc => {
+                self.search = None;
+            },
+            KeyCode::Enter | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End => {
+                self.accept_search();
+            },
+            _ => {},
+        }
+    }
+
+    /// replaces the text behind the cursor with its tab completion, returns if anything changed
+    /// nothing is completed while searching, the line under the search isn't shown
+    fn complete(&mut self) -> bool {
+        if self.is_searching() { return false; }
+
+        match complete_input(self.text_before_cursor()) {
+            Some(completed) => {
+                self.replace_text_before_cursor(&completed);
+                true
+            },
+            None => false,
+        }
+    }
+}
 
-            wipe_input();
+fn width_of(graphemes: &[(usize, &str)]) -> usize {
+    graphemes.iter().map(|(_, grapheme)| grapheme.width()).sum()
+}
+
+/// rewrites the whole file, so it never holds more than what is kept in memory
+fn save_history_file(path: &Path, history: &[String]) {
+    let mut contents = history.join("\n");
+    contents.push('\n');
+    if let Err(e) = fs::write(path, contents) {
+        log_warn!("failed to save command history: {}", e);
+    }
+}
 
-            // putting all the input chars into chars_behind_cursor
-            chars_ahead_cursor.reverse();
-            chars_behind_cursor.append(chars_ahead_cursor);
+/// handles what happens in accordance to input key event
+/// returns an input when enter is pressed
+pub fn key_output(event: KeyEvent, input_line: &mut InputLine) -> Option<String> {
+    let _ = std_out().queue(cursor::RestorePosition);
 
-            current_input = Some(chars_behind_cursor.iter().collect());
+    let current_input = input_line.apply_key(event);
+    if current_input.is_some() {
+        wipe_input();
+    }
 
-            chars_behind_cursor.clear();
-            chars_ahead_cursor.clear();
-            unsafe { BEHIND_PRINT_AMOUNT = MAX_INPUT_CHARS };
-        },
-        _ => {} 
-    };
+    print_input_text(input_line);
 
     current_input
-}
\ No newline at end of file
+}
+
+/// replaces the text behind the cursor with its tab completion, if it has one
+pub fn tab_complete(input_line: &mut InputLine) {
+    if input_line.complete() {
+        print_input_text(input_line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{command_registry, CommandInfo, GLOBALS_LOCK};
+
+    use super::*;
+
+    fn press(input_line: &mut InputLine, code: KeyCode) -> Option<String> {
+        input_line.apply_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn press_ctrl(input_line: &mut InputLine, code: KeyCode) -> Option<String> {
+        input_line.apply_key(KeyEvent::new(code, KeyModifiers::CONTROL))
+    }
+
+    fn type_text(input_line: &mut InputLine, text: &str) {
+        for c in text.chars() {
+            press(input_line, KeyCode::Char(c));
+        }
+    }
+
+    /// a line with its own history file holding history, removed when the test is done with it
+    struct TestLine {
+        input_line: InputLine,
+        path: PathBuf,
+    }
+
+    impl TestLine {
+        fn new(name: &str, history: &[&str]) -> Self {
+            let path = std::env::temp_dir().join(format!("conveyor_line_history_{}_{}.txt", name, std::process::id()));
+            fs::write(&path, history.join("\n")).unwrap();
+            Self { input_line: InputLine::with_history_file(&path), path }
+        }
+    }
+
+    impl Drop for TestLine {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    #[test]
+    fn history_is_saved_and_loaded_back() {
+        let history: Vec<String> = (0..COMMAND_HISTORY_CAP + 5).map(|i| format!("look {}", i)).collect();
+        let mut test_line = TestLine::new("persistence", &history.iter().map(String::as_str).collect::<Vec<_>>());
+
+        // only the newest lines are loaded
+        assert_eq!(test_line.input_line.history.len(), COMMAND_HISTORY_CAP);
+        assert_eq!(test_line.input_line.history[0], "look 5");
+
+        type_text(&mut test_line.input_line, "place north");
+        assert_eq!(press(&mut test_line.input_line, KeyCode::Enter).as_deref(), Some("place north"));
+        // blank lines and repeats aren't saved
+        press(&mut test_line.input_line, KeyCode::Enter);
+        type_text(&mut test_line.input_line, "place north");
+        press(&mut test_line.input_line, KeyCode::Enter);
+
+        let saved = fs::read_to_string(&test_line.path).unwrap();
+        assert_eq!(saved.lines().count(), COMMAND_HISTORY_CAP);
+        assert_eq!(saved.lines().last(), Some("place north"));
+
+        let reloaded = InputLine::with_history_file(&test_line.path);
+        assert_eq!(reloaded.history, test_line.input_line.history);
+    }
+
+    #[test]
+    fn word_jumps_skip_spaces() {
+        let mut test_line = TestLine::new("words", &[]);
+        let input_line = &mut test_line.input_line;
+        type_text(input_line, "place  north conveyor");
+
+        press(input_line, KeyCode::Home);
+        press_ctrl(input_line, KeyCode::Right);
+        assert_eq!(input_line.cursor, "place".len());
+        press_ctrl(input_line, KeyCode::Right);
+        assert_eq!(input_line.cursor, "place  north".len());
+        press_ctrl(input_line, KeyCode::Left);
+        assert_eq!(input_line.cursor, "place  ".len());
+
+        press_ctrl(input_line, KeyCode::Delete);
+        assert_eq!(input_line.text, "place   conveyor");
+        press(input_line, KeyCode::End);
+        press_ctrl(input_line, KeyCode::Backspace);
+        assert_eq!(input_line.text, "place   ");
+        press_ctrl(input_line, KeyCode::Backspace);
+        assert_eq!(input_line.text, "");
+    }
+
+    #[test]
+    fn cursor_moves_and_deletes_whole_graphemes() {
+        let mut test_line = TestLine::new("graphemes", &[]);
+        let input_line = &mut test_line.input_line;
+        // an e with a combining accent and a family emoji joined with zero width joiners
+        type_text(input_line, "ae\u{301}b\u{1F469}\u{200D}\u{1F467}");
+
+        press(input_line, KeyCode::Backspace);
+        assert_eq!(input_line.text, "ae\u{301}b");
+
+        press(input_line, KeyCode::Left);
+        press(input_line, KeyCode::Left);
+        assert_eq!(input_line.cursor, "a".len());
+        assert_eq!(input_line.grapheme_at_cursor(), Some("e\u{301}"));
+
+        press(input_line, KeyCode::Delete);
+        assert_eq!(input_line.text, "ab");
+        press(input_line, KeyCode::Right);
+        type_text(input_line, "\u{301}");
+        assert_eq!(input_line.text, "ab\u{301}");
+        assert_eq!(input_line.cursor, input_line.text.len());
+    }
+
+    #[test]
+    fn ctrl_r_searches_the_history_newest_first() {
+        let mut test_line = TestLine::new("search", &["place north a", "look", "place east b"]);
+        let input_line = &mut test_line.input_line;
+        type_text(input_line, "draft");
+
+        press_ctrl(input_line, KeyCode::Char('r'));
+        assert!(input_line.is_searching());
+        type_text(input_line, "place");
+        assert_eq!(input_line.search_match(), Some("place east b"));
+        press_ctrl(input_line, KeyCode::Char('r'));
+        assert_eq!(input_line.search_match(), Some("place north a"));
+        // there's nothing older, so it stays on the last match
+        press_ctrl(input_line, KeyCode::Char('r'));
+        assert_eq!(input_line.search_match(), Some("place north a"));
+
+        // escape leaves what was typed alone
+        press(input_line, KeyCode::Esc);
+        assert!(!input_line.is_searching());
+        assert_eq!(input_line.text, "draft");
+
+        press_ctrl(input_line, KeyCode::Char('r'));
+        type_text(input_line, "loo");
+        assert_eq!(press(input_line, KeyCode::Enter), None);
+        assert!(!input_line.is_searching());
+        assert_eq!(input_line.text, "look");
+        assert_eq!(input_line.cursor, "look".len());
+    }
+
+    #[test]
+    fn tab_completion_is_off_while_searching() {
+        let _globals = GLOBALS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        command_registry().insert(String::from("teleport"), CommandInfo {
+            name: String::from("teleport"),
+            usage: String::from("teleport"),
+            description: String::new(),
+            args: Vec::new(),
+            aliases: Vec::new(),
+        });
+
+        let mut test_line = TestLine::new("completion", &["tel"]);
+        let input_line = &mut test_line.input_line;
+        type_text(input_line, "tel");
+
+        press_ctrl(input_line, KeyCode::Char('r'));
+        assert!(!input_line.complete());
+        assert_eq!(input_line.text, "tel");
+
+        press(input_line, KeyCode::Esc);
+        assert!(input_line.complete());
+        assert!(input_line.text.starts_with("teleport"));
+
+        command_registry().remove("teleport");
+    }
+}
@@ -14,6 +14,7 @@ pub struct ItemStack {
 #[derive(Debug)]
 pub struct ItemType {
     pub identifier: u16,
+    pub name: String,
     pub text_display: TextDisplay,
     pub color_display: ColorDisplay
 }
\ No newline at end of file
@@ -0,0 +1,146 @@
+use rlua::{TablePairs, TableSequence, Value};
+use serde::de::{self, value::Error, Visitor};
+
+/// deserializes lua data into the same structures as module files
+/// tables are read as arrays or maps depending on what the field wants, so an empty table works as either
+/* rlua_serde does this but it's built against an older rlua ~*/
+pub struct LuaDeserializer<'lua> {
+    pub value: Value<'lua>,
+}
+
+fn lua_error(e: rlua::Error) -> Error {
+    de::Error::custom(e)
+}
+
+impl<'lua, 'de> de::Deserializer<'de> for LuaDeserializer<'lua> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Nil => visitor.visit_unit(),
+            Value::Boolean(boolean) => visitor.visit_bool(boolean),
+            Value::Integer(integer) => visitor.visit_i64(integer),
+            // so whole numbers still deserialize into integer fields
+            Value::Number(number) if number.fract() == 0.0 && number.abs() < i64::MAX as f64 => visitor.visit_i64(number as i64),
+            Value::Number(number) => visitor.visit_f64(number),
+            Value::String(string) => visitor.visit_str(string.to_str().map_err(lua_error)?),
+            Value::Table(table) => {
+                let mut map = MapDeserializer { pairs: table.pairs(), value: None };
+                visitor.visit_map(&mut map)
+            },
+            value => Err(de::Error::custom(format!("can't deserialize a {}", value.type_name()))),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Nil => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            Value::Table(table) => {
+                let length = table.raw_len();
+                let mut seq = SeqDeserializer(table.clone().sequence_values());
+                let value = visitor.visit_seq(&mut seq)?;
+
+                // anything past the sequence would be silently dropped
+                if table.pairs::<Value, Value>().count() as i64 != length {
+                    return Err(de::Error::custom("expected an array, found a table with keys other than 1..n"));
+                }
+                Ok(value)
+            },
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+        byte_buf unit unit_struct newtype_struct map struct enum identifier ignored_any
+    }
+}
+
+struct SeqDeserializer<'lua>(TableSequence<'lua, Value<'lua>>);
+
+impl<'lua, 'de> de::SeqAccess<'de> for SeqDeserializer<'lua> {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+        match self.0.next() {
+            Some(value) => seed.deserialize(LuaDeserializer { value: value.map_err(lua_error)? }).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+struct MapDeserializer<'lua> {
+    pairs: TablePairs<'lua, Value<'lua>, Value<'lua>>,
+    value: Option<Value<'lua>>,
+}
+
+impl<'lua, 'de> de::MapAccess<'de> for MapDeserializer<'lua> {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        match self.pairs.next() {
+            Some(pair) => {
+                let (key, value) = pair.map_err(lua_error)?;
+                self.value = Some(value);
+                seed.deserialize(LuaDeserializer { value: key }).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(LuaDeserializer { value }),
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rlua::Lua;
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Data {
+        name: String,
+        bytes: Option<Vec<u8>>,
+        empty: Option<Vec<u8>>,
+        weight: Option<u16>,
+    }
+
+    #[test]
+    fn tables_become_arrays_or_maps_as_needed() {
+        Lua::new().context(|lua_context| {
+            let value = lua_context.load(r#"{ name = "pebble", bytes = { 1, 2, 3 }, empty = {}, weight = 4.0 }"#).eval().unwrap();
+            let data = Data::deserialize(LuaDeserializer { value }).unwrap();
+
+            assert_eq!(data, Data { name: "pebble".to_owned(), bytes: Some(vec![1, 2, 3]), empty: Some(Vec::new()), weight: Some(4) });
+        });
+    }
+
+    #[test]
+    fn tables_with_holes_arent_arrays() {
+        Lua::new().context(|lua_context| {
+            let value = lua_context.load(r#"{ name = "pebble", bytes = { 1, 2, [5] = 3 } }"#).eval().unwrap();
+
+            assert!(Data::deserialize(LuaDeserializer { value }).is_err());
+        });
+    }
+}
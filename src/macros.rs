@@ -49,4 +49,32 @@ macro_rules! ignorant_flush {
     ($writer:expr) => {
         let _ = $writer().flush();
     };
-}
\ No newline at end of file
+}
+/// Logs to the log file with the calling module as the target
+/// # Example
+/// ```
+/// log_warn!("Failed to load {}!", name);
+/// ```
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        $crate::write_log($crate::Severity::Debug, module_path!(), format!($($arg)*))
+    };
+}
+
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        $crate::write_log($crate::Severity::Info, module_path!(), format!($($arg)*))
+    };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        $crate::write_log($crate::Severity::Warn, module_path!(), format!($($arg)*))
+    };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        $crate::write_log($crate::Severity::Error, module_path!(), format!($($arg)*))
+    };
+}
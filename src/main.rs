@@ -1,9 +1,10 @@
 use core::num;
-use std::{collections::HashMap, env, error::Error, fmt::Display, fs, io::Stdout, process, sync::{Arc, Mutex}, time::{Duration, SystemTime}};
+use std::{collections::{BTreeMap, HashMap}, env, error::Error, fmt::Display, fs, io::Stdout, process, sync::{Arc, Mutex}, time::{Duration, Instant, SystemTime}};
 
 use crossterm::{cursor, event::{poll, read, Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers}, execute, queue, terminal::{enable_raw_mode, Clear, ClearType}, QueueableCommand};
 use json::{object::Object, JsonValue};
 use once_cell::sync::Lazy;
+use rand::Rng;
 use rlua::{Context, Function, Lua, Table, ToLua, ToLuaMulti, Value};
 use tui::{backend::CrosstermBackend, Terminal};
 
@@ -49,6 +50,10 @@ const PLAYER_COORD_DISPLAY: (u16, u16) = (RIGHT_TOP_LEFT.0 + 2, RIGHT_TOP_LEFT.1
 
 const START_OF_INPUT_LINE: (u16, u16) = (27, LEFT_BOTTOM_TOP_LEFT.1 + 2);
 
+const MESSAGE_LOG_TOP_LEFT: (u16, u16) = (RIGHT_BOTTOM_TOP_LEFT.0 + 1, RIGHT_BOTTOM_TOP_LEFT.1 + 1);
+const MESSAGE_LOG_WIDTH: u16 = RIGHT_BOX_WIDTH - 2;
+const MESSAGE_LOG_HEIGHT: u16 = BOTTOM_BOX_HEIGHT + 2;
+
 mod debug;
 use debug::*;
 
@@ -75,18 +80,41 @@ use script_processing::*;
 mod module_loading;
 use module_loading::*;
 
+mod lua_deserializer;
+
+mod module_diagnostics;
+use module_diagnostics::*;
+
+mod module_schema;
+use module_schema::*;
+
 mod player;
 use player::*;
 
+mod entity;
+use entity::*;
+
 mod map;
 use map::*;
 
 mod map_render;
 use map_render::*;
 
+mod tick_clock;
+use tick_clock::*;
+
+mod world;
+use world::*;
+
+mod replay;
+use replay::*;
+
 mod input;
 use input::*;
 
+mod message_log;
+use message_log::*;
+
 mod commands;
 use commands::*;
 
@@ -97,16 +125,16 @@ fn lua() -> Arc<Mutex<Lua>> {
     unsafe { LUA.clone().expect("Shouldn't be None") }
 }
 
+/// tests which use the game's globals hold this, since tests run at the same time
+#[cfg(test)]
+static GLOBALS_LOCK: Mutex<()> = Mutex::new(());
+
 // flag for if map needs to be redrawn
 static mut STATE_CHANGED: bool = true;
 
-static mut TIME_BETWEEN_TICKS: Option<Duration> = None;
-fn time_between_ticks() -> Duration {
-    unsafe { TIME_BETWEEN_TICKS.unwrap() }
-}
-static mut LAST_TICK: Option<SystemTime> = None;
-fn last_tick() -> SystemTime {
-    unsafe { LAST_TICK.unwrap() }
+/// how many ticks have run since the game started
+fn tick_count() -> u64 {
+    world().tick()
 }
 
 static mut TERMINAL: Option<Arc<Mutex<Terminal<CrosstermBackend<Stdout>>>>> = None;
@@ -115,72 +143,70 @@ fn terminal() -> Arc<Mutex<Terminal<CrosstermBackend<Stdout>>>> {
 }
 
 fn main() {
-    clear_debug();
+    init_logging();
+    init_tick_clock();
+
+    let replay = env::args().position(|arg| arg == "--replay")
+        .map(|position| env::args().nth(position + 1).unwrap_or_default())
+        .map(|path| Replay::load(&path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        }));
+
+    // the same seed and inputs always give the same world
+    let seed = replay.as_ref().map(|replay| replay.seed)
+        .or_else(|| env::args().position(|arg| arg == "--seed")
+            .and_then(|position| env::args().nth(position + 1))
+            .and_then(|seed| seed.parse().ok()))
+        .unwrap_or_else(World::random_seed);
+    world().reseed(seed);
+    log_info!("world seed {}", seed);
 
-    println!("program started");
-    enable_raw_mode().unwrap();
-
-    // lua init
-    unsafe {
-        LUA = Some(Arc::new(Mutex::new(Lua::new())))
-    }
     unsafe {
         TERMINAL = Some(Arc::new(Mutex::new(Terminal::new(CrosstermBackend::new(stdout())).expect("Shouldn't"))))
     }
 
-    lua().lock().unwrap().context(|lua_context| {
-        load_default_lua_data(lua_context);
-    });
+    let script_errors = init_lua();
 
-    run_lua_scripts_from_path(MODULES_PATH, lua());
+    if env::args().any(|arg| arg == "--schema") {
+        process::exit(write_module_reference());
+    }
 
-    // game data init
-    unsafe { 
-        MAPPED_DUMP = {
-            let mapped_dump;
-            
-            match load_module_data_from_persistent_mapping(MOD_PACK_MAPPINGS_PATH) {
-                Ok(_) => todo!(),
-                Err(_) => {
-                    let mut deserial_dump: DeserializationDump = HashMap::new();
-                    deserialize_modules_from_path(&mut deserial_dump, MODULES_PATH);
+    if env::args().any(|arg| arg == "--check") {
+        process::exit(check_modules(script_errors));
+    }
 
-                    let mut pre_map_dump: PreMapDump = Vec::new();
-                    mapped_dump = map_deserialized_dump(&mut pre_map_dump, &deserial_dump, &mut ID_TRACKER );
-                },
-            }
+    println!("program started");
+    log_info!("program started");
+    // replays have no one to show the game to
+    if replay.is_none() {
+        enable_raw_mode().unwrap();
+    }
 
-            write_to_debug_pretty(format!("\nfinal dump:\n{:?}\n", mapped_dump));
-            Some(mapped_dump)
-        }
-    };
+    let mut module_load_errors = init_game_data();
+    module_load_errors.splice(0..0, script_errors);
 
-    // game world init
-    unsafe {
-        TILE_MAP = {
-            let mut map_rand_tile_gen = WeightedRandomBuilder::new();
-        
-            for (id, tile_type) in &game_data_dump().tile_types {
-                if tile_type.world_gen_weight > 0.0 {
-                    map_rand_tile_gen.add_entry(id, tile_type.world_gen_weight);
-                }
-            }
-            
-            Some(gen_map(map_rand_tile_gen.finalize(), |x| Tile::new_unchecked(*x)))
+    if replay.is_some() {
+        for error in &module_load_errors {
+            eprintln!("{}", error);
         }
+    } else if !module_load_errors.is_empty() {
+        display_module_load_errors(&module_load_errors);
     }
 
-    unsafe {
-        GROUND_MAP = {
-            let mut map_rand_ground_gen = WeightedRandomBuilder::new();
-        
-            for (id, ground_type) in &game_data_dump().ground_types {
-                if ground_type.world_gen_weight > 0.0 {
-                    map_rand_ground_gen.add_entry(id, ground_type.world_gen_weight);
-                }
-            }
-        
-            Some(gen_map(map_rand_ground_gen.finalize(), |x| Ground::new(*x)))
+    init_world();
+
+    if let Some(replay) = replay {
+        process::exit(run_replay(&replay));
+    }
+
+    if let Some(path) = env::args().position(|arg| arg == "--record").and_then(|position| env::args().nth(position + 1)) {
+        match Recorder::create(&path, seed) {
+            Ok(recorder) => start_recording(recorder),
+            Err(e) => {
+                eprintln!("couldn't record to {}: {}", path, e);
+                process::exit(1);
+            },
         }
     }
     
@@ -188,11 +214,6 @@ fn main() {
     ignorant_execute!(std_out, cursor::Hide);
     ignorant_queue!(std_out, cursor::MoveTo(0, 0));
     ignorant_queue!(std_out, Clear(ClearType::FromCursorDown)); 
-    
-    /* delete when you make world gen good ~*/
-    tile_map()[player().position.0][player().position.1] = Tile::new_unchecked({
-        identifier_dump().tile_types.get_by_left("conveyor_line_core:air").cloned().unwrap_or(0)
-    });
 
     /* TODO - make all the ui boxes defined through lua script 
      * also make them work good universally (text wrap and nav controls)
@@ -206,31 +227,27 @@ fn main() {
     let mut cursor_state = true;
     let mut last_cursor_update = SystemTime::now();
     
-    let mut chars_behind_cursor: Vec<char> = Vec::new();
-    let mut chars_ahead_cursor: Vec<char> = Vec::new();
+    let mut input_line = InputLine::new();
     
     let mut type_mode = false;
 
-    unsafe { TIME_BETWEEN_TICKS = Some(Duration::from_millis(50)) };
-    unsafe { LAST_TICK = Some(SystemTime::now()) };
-    
     loop {
-        let tick_time_diff = match last_tick().elapsed() {
-            Ok(elapsed) => elapsed,
-            Err(future_elapsed) => future_elapsed.duration(),
-        };
-        if tick_time_diff >= time_between_ticks() {
-            let amount_of_ticks_to_run = tick_time_diff.as_millis() / time_between_ticks().as_millis();
-
-            for _ in 0..amount_of_ticks_to_run {
-                call_lua_events("TickEvents", ());
-                unsafe { LAST_TICK = Some(SystemTime::now()) };
+        let due_ticks = tick_clock().due_ticks(Instant::now());
+        if due_ticks > 0 {
+            for _ in 0..due_ticks {
+                step();
             }
+
+            call_lua_events("RenderEvents", ());
+
+            animate_tile_map(tick_count());
         }
 
         if unsafe {STATE_CHANGED} { 
-            display_play_info(&chars_behind_cursor);
+            display_play_info(&mut input_line);
             unsafe {STATE_CHANGED = false;}
+        } else if !dirty_cells().is_empty() {
+            display_dirty_cells(MAP_TOP_LEFT);
         }
 
         ignorant_queue!(std_out, cursor::RestorePosition);
@@ -240,18 +257,11 @@ fn main() {
             true if type_mode => {
                 print!("{}", cursor_mimic);
 
-                ignorant_execute!(std_out, cursor::MoveLeft(cursor_mimic.len() as u16));
+                ignorant_execute!(std_out, cursor::MoveLeft(1));
             },
             false => {
-                if chars_ahead_cursor.is_empty() {
-                    print!(" ");
-                } else {
-                    if behind_print_amount() >= MAX_INPUT_CHARS {
-                        print!("{}", RIGHT_ARROW_WHITE);
-                    } else {
-                        print!("{}", chars_ahead_cursor[chars_ahead_cursor.len() - 1]);
-                    }
-                }
+                print!("{}", input_line.grapheme_at_cursor().unwrap_or(" "));
+
                 ignorant_execute!(std_out, cursor::MoveLeft(1));
             },
             _ => {}
@@ -269,25 +279,41 @@ fn main() {
                 Ok(Event::Key(event)) if event.kind == KeyEventKind::Press => {
                     match (event.code, type_mode) {
                         (KeyCode::Char('c'), _) if event.modifiers == KeyModifiers::CONTROL => {
+                            finish_recording();
                             std::process::exit(0);
                         },
+                        (KeyCode::PageUp, _) => {
+                            message_log().scroll_up(1);
+                        },
+                        (KeyCode::PageDown, _) => {
+                            message_log().scroll_down(1);
+                        },
                         (KeyCode::F(1) | KeyCode::Tab, false) => {
                             type_mode = true;
                         },
-                        (KeyCode::F(1) | KeyCode::Tab, true) => {
+                        (KeyCode::F(1) | KeyCode::Esc, true) if !input_line.is_searching() => {
                             type_mode = false;
                         },
+                        (KeyCode::Tab, true) => {
+                            tab_complete(&mut input_line);
+                        },
                         (_, true) => {
                             // key_output returns the input command when enter is pressed
-                            let output = key_output(event, &mut chars_behind_cursor, &mut chars_ahead_cursor);
+                            let output = key_output(event, &mut input_line);
                             match output {
                                 Some(current_input) => {
+                                    if let Some(recorder) = recorder() {
+                                        recorder.record(RecordedInput::Command(current_input.clone()));
+                                    }
                                     action_from_input( &current_input);
                                 },
                                 None => {},
                             }
                         },
                         (_, false) => {
+                            if let Some(recorder) = recorder() {
+                                recorder.record(RecordedInput::Key(event));
+                            }
                             call_key_events(&event)
                         }
                     }
@@ -299,13 +325,160 @@ fn main() {
     
 }
 
-fn display_play_info(chars_behind_cursor: &Vec<char>) {
+/// sets up lua and runs every module script, returns the scripts which failed
+fn init_lua() -> Vec<ModuleLoadError> {
+    unsafe {
+        LUA = Some(Arc::new(Mutex::new(Lua::new())))
+    }
+
+    lua().lock().unwrap().context(|lua_context| {
+        load_default_lua_data(lua_context);
+    });
+
+    run_lua_scripts_from_path(MODULES_PATH, lua())
+}
+
+/// loads and maps the data of every module, returns everything which failed to load
+// the id allocators are only used here, on the main thread
+#[allow(static_mut_refs)]
+fn init_game_data() -> Vec<ModuleLoadError> {
+    let module_load_errors;
+    unsafe { 
+        MAPPED_DUMP = {
+            let mapped_dump;
+            
+            match load_module_data_from_persistent_mapping(MOD_PACK_MAPPINGS_PATH) {
+                Ok(_) => todo!(),
+                Err(_) => {
+                    (mapped_dump, module_load_errors) = load_modules(MODULES_PATH, &mut ID_ALLOCATORS);
+                },
+            }
+
+            log_debug!("final dump:\n{:#?}", mapped_dump);
+            Some(mapped_dump)
+        }
+    };
+
+    module_load_errors
+}
+
+/// generates the world from its rng, needs lua and the game data
+fn init_world() {
+    call_data_handlers();
+
+    // game world init
+    unsafe {
+        TILE_MAP = {
+            let mut map_rand_tile_gen = WeightedRandomBuilder::seeded(world().rng.gen());
+        
+            // by identifier, so the same seed gives the same map however ids were handed out
+            for (_, id) in identifier_dump().tile_types.iter().collect::<BTreeMap<_, _>>() {
+                let tile_type = &game_data_dump().tile_types[id];
+                if tile_type.world_gen_weight > 0.0 {
+                    map_rand_tile_gen.add_entry(id, tile_type.world_gen_weight);
+                }
+            }
+            
+            Some(gen_map(map_rand_tile_gen.finalize(), |x| Tile::new_unchecked(*x)))
+        }
+    }
+
+    unsafe {
+        GROUND_MAP = {
+            let mut map_rand_ground_gen = WeightedRandomBuilder::seeded(world().rng.gen());
+        
+            for (_, id) in identifier_dump().ground_types.iter().collect::<BTreeMap<_, _>>() {
+                let ground_type = &game_data_dump().ground_types[id];
+                if ground_type.world_gen_weight > 0.0 {
+                    map_rand_ground_gen.add_entry(id, ground_type.world_gen_weight);
+                }
+            }
+        
+            Some(gen_map(map_rand_ground_gen.finalize(), |x| Ground::new_unchecked(*x)))
+        }
+    }
+
+    call_world_gen_events();
+    
+    /* delete when you make world gen good ~*/
+    tile_map().set(player().position, Tile::new_unchecked({
+        identifier_dump().tile_types.get_by_left("conveyor_line_core:air").cloned().unwrap_or(0)
+    }));
+}
+
+/// runs module loading without starting the game, for --check
+/// prints every problem and returns the exit code
+fn check_modules(script_errors: Vec<ModuleLoadError>) -> i32 {
+    let mut errors = script_errors;
+    errors.extend(validate_modules(MODULES_PATH));
+
+    for error in &errors {
+        eprintln!("{}", error);
+    }
+
+    if errors.is_empty() {
+        println!("no problems found in {}", MODULES_PATH);
+        0
+    } else {
+        eprintln!("{} problem(s) found in {}", errors.len(), MODULES_PATH);
+        1
+    }
+}
+
+/// writes the module data reference for --schema, returns the exit code
+fn write_module_reference() -> i32 {
+    match fs::write(MODULE_REFERENCE_PATH, module_reference_markdown()) {
+        Ok(_) => {
+            println!("wrote {}", MODULE_REFERENCE_PATH);
+            0
+        },
+        Err(e) => {
+            eprintln!("couldn't write {}: {}", MODULE_REFERENCE_PATH, e);
+            1
+        },
+    }
+}
+
+/// lists everything which failed to load and waits for a key press before starting
+/// pressing q quits instead
+fn display_module_load_errors(errors: &[ModuleLoadError]) {
+    ignorant_queue!(std_out, cursor::MoveTo(0, 0));
+    ignorant_queue!(std_out, Clear(ClearType::All));
+
+    print!("{} problem(s) while loading modules, the affected data was skipped:\r\n\r\n", errors.len());
+    for error in errors {
+        for line in error.to_string().lines() {
+            print!("  {}\r\n", line);
+        }
+    }
+    print!("\r\npress any key to continue, or q to quit\r\n");
+    ignorant_execute!(std_out, cursor::Hide);
+
+    loop {
+        match read() {
+            Ok(Event::Key(event)) if event.kind == KeyEventKind::Press => {
+                if event.code == KeyCode::Char('q') {
+                    ignorant_execute!(std_out, cursor::Show);
+                    std::process::exit(1);
+                }
+                break;
+            },
+            Ok(_) => {},
+            Err(_) => break,
+        }
+    }
+}
+
+fn display_play_info(input_line: &mut InputLine) {
     ignorant_queue!(std_out, cursor::MoveTo(MAP_TOP_LEFT.0, MAP_TOP_LEFT.1));
     display_map();
     ignorant_queue!(std_out, cursor::MoveTo(PLAYER_COORD_DISPLAY.0, PLAYER_COORD_DISPLAY.1));
     println!("({}, {})", &player().position.0 , &player().position.1);
+
+    display_message_log();
     
-    ignorant_queue!(std_out, cursor::MoveTo(27, MAP_HEIGHT as u16 + 6 + behind_print_amount().min(chars_behind_cursor.len()) as u16));
+    let (_, cursor_column, _, _) = input_line.visible_text(MAX_INPUT_CHARS);
+    ignorant_queue!(std_out, cursor::MoveTo(START_OF_INPUT_LINE.0 + cursor_column as u16, START_OF_INPUT_LINE.1));
     ignorant_queue!(std_out, cursor::SavePosition);
 }
 
@@ -316,33 +489,13 @@ fn wipe_input() {
 }
 
 /// displays the input feed as scrolling text
-fn print_input_text(chars_behind_cursor: &Vec<char>, chars_ahead_cursor: &Vec<char>) {
+fn print_input_text(input_line: &mut InputLine) {
     wipe_input();
 
-    ignorant_execute!(std_out, cursor::MoveTo(START_OF_INPUT_LINE.0, START_OF_INPUT_LINE.1));
-
-    
-    let behind_start_index = if chars_behind_cursor.len() > behind_print_amount() {
-        chars_behind_cursor.len() - behind_print_amount()
-    } else { 0 };
-    for i in behind_start_index..chars_behind_cursor.len() {
-        print!("{}", chars_behind_cursor[i]);
-    }
-    
-    let show_left_arrow = behind_start_index > 0;
-    
-    ignorant_execute!(std_out, cursor::SavePosition);
+    let (visible, cursor_column, show_left_arrow, show_right_arrow) = input_line.visible_text(MAX_INPUT_CHARS);
 
-    let gap_ahead = MAX_INPUT_CHARS - behind_print_amount();
-    let mut show_right_arrow = gap_ahead < chars_ahead_cursor.len();
-    for (i, c) in chars_ahead_cursor.iter().rev().enumerate() {
-        if i < gap_ahead {
-            print!("{}", c);
-        } else {
-            show_right_arrow = show_right_arrow || i < chars_ahead_cursor.len() - 1;
-            break;
-        }
-    }
+    ignorant_execute!(std_out, cursor::MoveTo(START_OF_INPUT_LINE.0, START_OF_INPUT_LINE.1));
+    print!("{}", visible);
 
     // show arrows which indicate that text is being wrapped
     if show_left_arrow {
@@ -353,4 +506,7 @@ fn print_input_text(chars_behind_cursor: &Vec<char>, chars_ahead_cursor: &Vec<ch
         ignorant_execute!(std_out, cursor::MoveTo(START_OF_INPUT_LINE.0 + MAX_INPUT_CHARS as u16, START_OF_INPUT_LINE.1));
         print!("{}", RIGHT_ARROW_WHITE);
     }
-}
\ No newline at end of file
+
+    ignorant_execute!(std_out, cursor::MoveTo(START_OF_INPUT_LINE.0 + cursor_column as u16, START_OF_INPUT_LINE.1));
+    ignorant_execute!(std_out, cursor::SavePosition);
+}
@@ -1,10 +1,10 @@
 
-use crate::{tile::Tile, Ground, WeightedRandom, MAP_HEIGHT, MAP_LENGTH};
+use crate::{entities, game_data_dump, mark_dirty, tile::Tile, Ground, WeightedRandom, MAP_HEIGHT, MAP_LENGTH};
 
-pub static mut TILE_MAP: Option<Vec<Vec<Tile>>> = None;
+pub static mut TILE_MAP: Option<Map<Tile>> = None;
 /// safe unsafe action lolz
 /// ONLY CALL IF TILE_MAP IS SOME(_)
-pub fn tile_map() -> &'static mut Vec<Vec<Tile>> { 
+pub fn tile_map() -> &'static mut Map<Tile> { 
     unsafe { 
         if let Some(tile_map) = TILE_MAP.as_mut() {
             tile_map
@@ -14,10 +14,10 @@ pub fn tile_map() -> &'static mut Vec<Vec<Tile>> {
     } 
 }
 
-pub static mut GROUND_MAP: Option<Vec<Vec<Ground>>> = None;
+pub static mut GROUND_MAP: Option<Map<Ground>> = None;
 /// safe unsafe action lolz
 /// ONLY CALL IF GROUND_MAP IS SOME(_)
-pub fn ground_map() -> &'static mut Vec<Vec<Ground>> { 
+pub fn ground_map() -> &'static mut Map<Ground> { 
     unsafe { 
         if let Some(ground_map) = GROUND_MAP.as_mut() {
             ground_map
@@ -27,8 +27,98 @@ pub fn ground_map() -> &'static mut Vec<Vec<Ground>> {
     } 
 }
 
-/// Generates a random 2d vec of Y from f(T)
-pub fn gen_map<T: Clone, Y>(mut weighted_random: WeightedRandom<T>, f: fn(T) -> Y) -> Vec<Vec<Y>> {
+/// A grid indexed by (x, y), where every access is bounds checked
+pub struct Map<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Map<T> {
+    /// rows are indexed by y, every row must be as long as the first
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Self {
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.len());
+        assert!(rows.iter().all(|row| row.len() == width), "map rows aren't all the same length");
+
+        Self {
+            width,
+            height,
+            cells: rows.into_iter().flatten().collect(),
+        }
+    }
+
+    pub fn contains(&self, position: (usize, usize)) -> bool {
+        position.0 < self.width && position.1 < self.height
+    }
+
+    pub fn get(&self, position: (usize, usize)) -> Option<&T> {
+        if !self.contains(position) { return None; }
+        self.cells.get(position.1 * self.width + position.0)
+    }
+
+    pub fn get_mut(&mut self, position: (usize, usize)) -> Option<&mut T> {
+        if !self.contains(position) { return None; }
+        self.cells.get_mut(position.1 * self.width + position.0)
+    }
+
+    /// returns what was there before, None and nothing changes if position is off the map
+    pub fn set(&mut self, position: (usize, usize), value: T) -> Option<T> {
+        self.get_mut(position).map(|cell| std::mem::replace(cell, value))
+    }
+
+    /// every cell with its position, row by row
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.cells.iter().enumerate().map(move |(i, cell)| ((i % width, i / width), cell))
+    }
+
+    /// every cell with its position, row by row
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = ((usize, usize), &mut T)> {
+        let width = self.width;
+        self.cells.iter_mut().enumerate().map(move |(i, cell)| ((i % width, i / width), cell))
+    }
+}
+
+pub fn on_map(position: (usize, usize)) -> bool {
+    position.0 < MAP_LENGTH && position.1 < MAP_HEIGHT
+}
+
+/// checks a position coming from lua, which can be anything including negative
+/// the error is meant to be returned to the script
+pub fn map_position(x: i64, y: i64) -> Result<(usize, usize), String> {
+    if x >= 0 && y >= 0 && on_map((x as usize, y as usize)) {
+        Ok((x as usize, y as usize))
+    } else {
+        Err(format!("({}, {}) is outside the map, which is {} by {}", x, y, MAP_LENGTH, MAP_HEIGHT))
+    }
+}
+
+/// if something can move to position
+/// cells off the map, solid ground, solid tiles and cells with an entity aren't passable
+pub fn is_passable(position: (usize, usize)) -> bool {
+    let (ground, tile) = match (ground_map().get(position), tile_map().get(position)) {
+        (Some(ground), Some(tile)) => (ground, tile),
+        _ => return false,
+    };
+
+    let ground_solid = game_data_dump().ground_types.get(&ground.ground_type).is_none_or(|ground_type| ground_type.solid);
+    let tile_solid = game_data_dump().tile_types.get(&tile.tile_type).is_none_or(|tile_type| tile_type.solid);
+
+    !ground_solid && !tile_solid && entities().at(position).is_none()
+}
+
+/// moves every tile's animation to tick, cells which look different afterwards are marked dirty
+pub fn animate_tile_map(tick: u64) {
+    for (position, tile) in tile_map().iter_mut() {
+        if tile.animate(tick) {
+            mark_dirty(position);
+        }
+    }
+}
+
+/// Generates a random map of Y from f(T)
+pub fn gen_map<T: Clone, Y>(mut weighted_random: WeightedRandom<T>, f: fn(T) -> Y) -> Map<Y> {
     let mut map: Vec<Vec<Y>> = Vec::new();
 
     for column in 0..MAP_HEIGHT {
@@ -40,5 +130,5 @@ pub fn gen_map<T: Clone, Y>(mut weighted_random: WeightedRandom<T>, f: fn(T) ->
             });
         }
     }
-    map
+    Map::from_rows(map)
 }
\ No newline at end of file
@@ -1,37 +1,80 @@
 
+use std::{collections::{BTreeSet, HashMap}, io::Write};
+
 use crossterm::cursor;
 
-use crate::{display::{color_format_char, ColorDisplay, HasBackColor, HasTextColor, HasTextDisplay, ANSI_DEFAULT_TEXT_COLOR}, ground_map, player::player, std_out, tile_map};
+use crate::{display::{color_format_char, ColorDisplay, HasBackColor, HasTextColor, HasTextDisplay, ANSI_DEFAULT_TEXT_COLOR}, entities, ground_map, player::player, std_out, tile_map, Entity};
 
 
 pub const MAP_LENGTH: usize = 26;
 pub const MAP_HEIGHT: usize = 26;
 
+/// cells which look different since the map was last drawn
+pub static mut DIRTY_CELLS: BTreeSet<(usize, usize)> = BTreeSet::new();
+/// safe unsafe action lolz
+// cells are marked and drawn on the main thread
+#[allow(static_mut_refs)]
+pub fn dirty_cells() -> &'static mut BTreeSet<(usize, usize)> { unsafe { &mut DIRTY_CELLS } }
+
+/// the cell is drawn again by the next display_dirty_cells, without redrawing the whole map
+pub fn mark_dirty(position: (usize, usize)) {
+    dirty_cells().insert(position);
+}
+
 /// Prints out the given map
 pub fn display_map() {
+    let entities_by_position = entities().by_position();
+
     for y in 0..MAP_HEIGHT {
         for x in 0..MAP_LENGTH {
-            // left and right sides of the tile rendering
-            let left: String;
-            let right: String;
-            
-            // the color data for the ground at current point
-            let ground_colors = ground_map()[y][x].ansi_back_colors();
-
-            // tile at current point
-            let tile = &tile_map()[y][x];
-            left = get_left_tile_display((x, y), tile, ground_colors);
-
-            right = get_right_tile_display(tile, ground_colors);
-
-
-            // print the left and right sides of the tile
-            print!("{}{}", left, right);
+            print!("{}", cell_display((x, y), &entities_by_position));
         }
         // new line every row
         ignorant_queue!(std_out, cursor::MoveDown(1));
         ignorant_execute!(std_out, cursor::MoveLeft(MAP_LENGTH as u16 * 2));
     }
+
+    // everything was just drawn
+    dirty_cells().clear();
+}
+
+/// Prints out only the cells marked dirty, map_top_left is where display_map starts drawing
+pub fn display_dirty_cells(map_top_left: (u16, u16)) {
+    let entities_by_position = entities().by_position();
+
+    for (x, y) in std::mem::take(dirty_cells()) {
+        ignorant_queue!(std_out, cursor::MoveTo(map_top_left.0 + x as u16 * 2, map_top_left.1 + y as u16));
+        print!("{}", cell_display((x, y), &entities_by_position));
+    }
+    ignorant_flush!(std_out);
+}
+
+/// the left and right sides of the cell at point, with the player, entity, tile and ground layers
+fn cell_display(point: (usize, usize), entities_by_position: &HashMap<(usize, usize), &Entity>) -> String {
+    // left and right sides of the tile rendering
+    let left: String;
+    let right: String;
+
+    // the color data for the ground at current point
+    let ground_colors = ground_map().get(point).expect("maps are MAP_LENGTH by MAP_HEIGHT").ansi_back_colors();
+
+    // entity at current point is drawn over the tile
+    match entities_by_position.get(&point) {
+        Some(entity) => {
+            left = get_left_tile_display(point, *entity, ground_colors);
+
+            right = get_right_tile_display(*entity, ground_colors);
+        },
+        None => {
+            // tile at current point
+            let tile = tile_map().get(point).expect("maps are MAP_LENGTH by MAP_HEIGHT");
+            left = get_left_tile_display(point, tile, ground_colors);
+
+            right = get_right_tile_display(tile, ground_colors);
+        },
+    }
+
+    format!("{}{}", left, right)
 }
 
 
@@ -55,7 +98,7 @@ fn get_left_tile_display<T: HasTextColor + HasBackColor + HasTextDisplay>(point:
             back_color,
             player().text_display.character_left.unwrap_or(' ')
         );
-    // display tile layer when player is not present
+    // display tile or entity layer when player is not present
     } else {
         // text color is either defined tile text color or default terminal color
         let text_color = match tile.ansi_text_colors().text_color_left {
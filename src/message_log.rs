@@ -0,0 +1,136 @@
+use std::collections::VecDeque;
+
+use crossterm::cursor;
+use once_cell::sync::Lazy;
+use unicode_width::UnicodeWidthChar;
+
+use crate::{std_out, tick_count, write_log, Severity, MESSAGE_LOG_HEIGHT, MESSAGE_LOG_TOP_LEFT, MESSAGE_LOG_WIDTH, STATE_CHANGED};
+
+/// how many messages are kept before the oldest are dropped
+const MESSAGE_LOG_CAP: usize = 200;
+
+pub static mut MESSAGE_LOG: Lazy<MessageLog> = Lazy::new(MessageLog::new);
+/// safe unsafe action lolz
+// only pushed to and drawn from the main thread
+#[allow(static_mut_refs)]
+pub fn message_log() -> &'static mut MessageLog { unsafe { &mut MESSAGE_LOG } }
+
+/// text color a message of this severity is shown in
+fn ansi_color(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Debug => "38;2;150;150;150",
+        Severity::Info => "38;2;255;255;255",
+        Severity::Warn => "38;2;250;197;7",
+        Severity::Error => "38;2;242;60;39",
+    }
+}
+
+/// A single entry in the message log
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub tick: u64,
+    pub severity: Severity,
+    pub text: String,
+}
+
+/// Messages shown to the player in the bottom right box
+pub struct MessageLog {
+    messages: VecDeque<Message>,
+    /// how many wrapped lines the view is scrolled up from the newest
+    scroll: usize,
+    pub min_severity: Severity,
+}
+
+impl MessageLog {
+    pub fn new() -> Self {
+        Self {
+            messages: VecDeque::new(),
+            scroll: 0,
+            min_severity: Severity::Info,
+        }
+    }
+
+    pub fn push(&mut self, severity: Severity, text: &str) {
+        write_log(severity, module_path!(), text);
+        if severity < self.min_severity { return; }
+
+        self.messages.push_back(Message {
+            tick: tick_count(),
+            severity,
+            text: text.to_owned(),
+        });
+        if self.messages.len() > MESSAGE_LOG_CAP {
+            self.messages.pop_front();
+        }
+
+        // new messages bring the view back down
+        self.scroll = 0;
+        unsafe { STATE_CHANGED = true; }
+    }
+
+    pub fn scroll_up(&mut self, amount: usize) {
+        let max_scroll = self.wrapped_lines().len().saturating_sub(MESSAGE_LOG_HEIGHT as usize);
+        self.scroll = (self.scroll + amount).min(max_scroll);
+        unsafe { STATE_CHANGED = true; }
+    }
+
+    pub fn scroll_down(&mut self, amount: usize) {
+        self.scroll = self.scroll.saturating_sub(amount);
+        unsafe { STATE_CHANGED = true; }
+    }
+
+    /// every message split into lines that fit the box, oldest first
+    fn wrapped_lines(&self) -> Vec<(Severity, String)> {
+        let mut lines = Vec::new();
+        for message in &self.messages {
+            for (i, text_line) in message.text.lines().enumerate() {
+                let prefixed = if i == 0 {
+                    match message.severity {
+                        Severity::Warn | Severity::Error => format!("[{}] {}: {}", message.tick, message.severity.label(), text_line),
+                        _ => format!("[{}] {}", message.tick, text_line),
+                    }
+                } else {
+                    format!("  {}", text_line)
+                };
+                for wrapped in wrap(&prefixed, MESSAGE_LOG_WIDTH as usize) {
+                    lines.push((message.severity, wrapped));
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// splits text into pieces no wider than width
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut pieces = vec![String::new()];
+    let mut used_width = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used_width + char_width > width {
+            pieces.push(String::new());
+            used_width = 0;
+        }
+        pieces.last_mut().unwrap().push(c);
+        used_width += char_width;
+    }
+    pieces
+}
+
+/// Prints the newest messages, or older ones when scrolled
+pub fn display_message_log() {
+    let lines = message_log().wrapped_lines();
+    let height = MESSAGE_LOG_HEIGHT as usize;
+    let end = lines.len().saturating_sub(message_log().scroll);
+    let start = end.saturating_sub(height);
+
+    for row in 0..height {
+        ignorant_queue!(std_out, cursor::MoveTo(MESSAGE_LOG_TOP_LEFT.0, MESSAGE_LOG_TOP_LEFT.1 + row as u16));
+        print!("{}", " ".repeat(MESSAGE_LOG_WIDTH as usize));
+        ignorant_queue!(std_out, cursor::MoveTo(MESSAGE_LOG_TOP_LEFT.0, MESSAGE_LOG_TOP_LEFT.1 + row as u16));
+
+        if let Some((severity, line)) = lines.get(start + row) {
+            print!("\u{001B}[{}m{}\u{001B}[0m", ansi_color(*severity), line);
+        }
+    }
+}
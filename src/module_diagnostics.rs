@@ -0,0 +1,114 @@
+use std::{fmt::Display, ops::Range, path::{Path, PathBuf}};
+
+/// What went wrong while loading a piece of module data
+#[derive(Clone, Debug)]
+pub enum ModuleLoadErrorKind {
+    /// a file or directory couldn't be read
+    Io(String),
+    /// a data file isn't valid or doesn't match the expected structure
+    Parse(String),
+    /// an entry which needs [visual_data] doesn't have any
+    MissingVisualData { data_type: String, name: String },
+    InvalidVisualData { name: String, reason: String },
+    /// a field without a default is missing
+    MissingField { data_type: String, name: String, field: String },
+    /// a lua script failed while running
+    Script(String),
+    /// a key which doesn't belong to anything, usually a typo
+    UnknownKey(String),
+    /// two entries with the same identifier and priority, so which one wins is arbitrary
+    DuplicateIdentifier { data_type: String, identifier: String, priority: u8, other: Option<PathBuf> },
+    /// a source which isn't the name of any loaded module
+    UnknownSource(String),
+    /// extends names an identifier which isn't defined anywhere
+    MissingParent { name: String, parent: String },
+    /// entries which extend each other in a loop, in order
+    InheritanceCycle(Vec<String>),
+    /// patch names an identifier which isn't defined anywhere
+    MissingPatchTarget(String),
+    /// name given to a single entry, which is named after its file instead
+    NameOutsideArray { key: String },
+    /// every id of a data type is already used, so the entry was skipped
+    OutOfIds { data_type: String, identifier: String },
+}
+
+/// A failure while loading modules, located as precisely as possible
+#[derive(Clone, Debug)]
+pub struct ModuleLoadError {
+    /// None for data which didn't come from a file, e.g. Core.InitializationInfo.GameData
+    pub path: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub kind: ModuleLoadErrorKind,
+}
+
+impl ModuleLoadError {
+    pub fn new(path: Option<&Path>, kind: ModuleLoadErrorKind) -> Self {
+        Self {
+            path: path.map(Path::to_path_buf),
+            line: None,
+            column: None,
+            kind,
+        }
+    }
+
+    /// line and column are 1 based
+    pub fn at_position(mut self, line: usize, column: usize) -> Self {
+        self.line = Some(line);
+        self.column = Some(column);
+        self
+    }
+
+    /// turns a byte span in source into a 1 based line and column
+    pub fn at_span(mut self, source: &str, span: Option<Range<usize>>) -> Self {
+        if let Some(span) = span {
+            let before = &source[..span.start.min(source.len())];
+            self.line = Some(before.matches('\n').count() + 1);
+            self.column = Some(before.chars().rev().take_while(|c| *c != '\n').count() + 1);
+        }
+        self
+    }
+}
+
+impl Display for ModuleLoadErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "couldn't read: {}", e),
+            Self::Parse(e) => write!(f, "{}", e),
+            Self::MissingVisualData { data_type, name } => write!(f, "{} '{}' has no [visual_data]", data_type, name),
+            Self::InvalidVisualData { name, reason } => write!(f, "invalid [visual_data] for '{}': {}", name, reason),
+            Self::MissingField { data_type, name, field } => write!(f, "{} '{}' is missing required field '{}'", data_type, name, field),
+            Self::Script(e) => write!(f, "script error: {}", e),
+            Self::UnknownKey(key) => write!(f, "unknown key '{}'", key),
+            Self::DuplicateIdentifier { data_type, identifier, priority, other } => {
+                write!(f, "{} '{}' is defined more than once with priority {}", data_type, identifier, priority)?;
+                match other {
+                    Some(other) => write!(f, ", also in {}", other.display()),
+                    None => write!(f, ", also in <lua>"),
+                }
+            },
+            Self::UnknownSource(source) => write!(f, "source '{}' isn't a loaded module", source),
+            Self::MissingParent { name, parent } => write!(f, "'{}' extends '{}', which doesn't exist", name, parent),
+            Self::InheritanceCycle(cycle) => write!(f, "inheritance cycle: {}", cycle.join(" extends ")),
+            Self::MissingPatchTarget(target) => write!(f, "patches '{}', which doesn't exist", target),
+            Self::NameOutsideArray { key } => write!(f, "'{}.name' is ignored, only entries in arrays are named, [{}] is named after its file", key, key),
+            Self::OutOfIds { data_type, identifier } => write!(f, "'{}' was skipped, there are more than {} {} definitions", identifier, u16::MAX as u32 + 1, data_type),
+        }
+    }
+}
+
+impl Display for ModuleLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}", path.display())?,
+            None => write!(f, "<lua>")?,
+        }
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
@@ -1,18 +1,18 @@
-use std::{any::Any, collections::HashMap, fs::{self, DirEntry}, io};
+use std::{any::Any, collections::{BTreeMap, HashMap}, fs, io, ops::Range, path::{Path, PathBuf}};
 use bimap::BiMap;
 use rlua::{Function, Table, Value};
 use serde::Deserialize;
 
-use crate::{dir_entry_is_dir, display::{ColorDisplay, ColorDisplayBuilder, TextDisplay, TextDisplayBuilder}, lua, os_string_to_string, write_to_debug, write_to_debug_pretty, GroundType, ItemType, TileType};
+use crate::{sorted_dir, lua_deserializer::LuaDeserializer, display::{Animation, ColorDisplay, ColorDisplayBuilder, TextDisplay, TextDisplayBuilder}, lua, os_string_to_string, GroundType, EntityType, schema_for, FieldSchema, ModuleLoadError, ModuleLoadErrorKind, SchemaType, ItemType, TileType};
 
-pub type DeserializationDump = HashMap<String, Vec<(String, ModuleDeserialization)>>;
+/// by module name, ordered so ids are handed out the same way every run
+pub type DeserializationDump = BTreeMap<String, Vec<(String, ModuleDeserialization)>>;
 pub type PreMapDump<'a> = Vec<UnmappedData<'a>>;
 pub type MappedGameData<T> = HashMap<u16, T>;
 
 pub const MOD_PACK_MAPPINGS_PATH: &str = r"resources/mod_pack_mappings";
 
-type IdTracker = (u16, u16, u16, u16, u16, u16);
-pub static mut ID_TRACKER: IdTracker = (0, 0, 0, 0, 0, 0);
+pub static mut ID_ALLOCATORS: IdAllocators = IdAllocators::new();
 pub static mut MAPPED_DUMP: Option<GameDataDump> = None;
 
 pub fn mapped_dump<'a>() -> &'a mut GameDataDump {
@@ -27,67 +27,165 @@ pub fn mapped_dump<'a>() -> &'a mut GameDataDump {
 pub fn game_data_dump<'a>() -> &'a mut MappedGameDatas { &mut mapped_dump().game_data } 
 pub fn identifier_dump<'a>() -> &'a mut IdentifierMaps { &mut mapped_dump().identifiers } 
 
+/// hands out the ids of one category of mapped data, counting up from 0
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IdAllocator {
+    /// wider than the ids, so running out can be told apart from the last id
+    next: u32,
+}
+
+impl IdAllocator {
+    pub const fn new() -> Self {
+        Self { next: 0 }
+    }
+
+    /// None once every u16 has been handed out
+    pub fn allocate(&mut self) -> Option<u16> {
+        let id = u16::try_from(self.next).ok()?;
+        self.next += 1;
+        Some(id)
+    }
+}
+
+/// one IdAllocator for every category in MappedGameDatas
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IdAllocators {
+    pub tile_types: IdAllocator,
+    pub ground_types: IdAllocator,
+    pub item_types: IdAllocator,
+    pub visible_thing_types: IdAllocator,
+    pub thing_types: IdAllocator,
+    pub byte_streams: IdAllocator,
+    pub entity_types: IdAllocator,
+}
+
+impl IdAllocators {
+    pub const fn new() -> Self {
+        Self {
+            tile_types: IdAllocator::new(),
+            ground_types: IdAllocator::new(),
+            item_types: IdAllocator::new(),
+            visible_thing_types: IdAllocator::new(),
+            thing_types: IdAllocator::new(),
+            byte_streams: IdAllocator::new(),
+            entity_types: IdAllocator::new(),
+        }
+    }
+}
+
 deserializable_module_data!{
-    struct VisualDeserData
+    pub struct VisualDeserData
 
+    /// not used yet
     identifier: String,
 
-    name: String,
+    /// the name shown to the player
+    name: String = "\"\"",
 
+    /// must not be empty, only the first character is used
     character_left: String,
+    /// must not be empty, only the first character is used
     character_right: String,
     
+    /// omitted colors use the terminal's default
     text_color_left: (u8, u8, u8),
     text_color_right: (u8, u8, u8),
     back_color_left: (u8, u8, u8),
-    back_color_right: (u8, u8, u8)
+    back_color_right: (u8, u8, u8),
+
+    /// shown one after another, unset fields are taken from the rest of this table. only tiles animate, anything else reports it
+    frames: Vec<VisualDeserData>,
+    /// how many ticks each frame is shown for, must be at least 1
+    frame_duration: u64 = "1"
 }
 
 deserializable_module_data!{
     [has_visual]
 
-    struct TileDeserialData
-    solid: bool,
-    world_gen_weight: f64
+    pub struct TileDeserialData
+    /// the entry's name in an array of entries, e.g. [[tiles]], files use their file name
+    name: String,
+    /// if the player can't walk through it
+    solid: bool = "true",
+    /// how often world generation places it, 0 means never
+    world_gen_weight: f64 = "0.0",
+    /// if it can be placed facing north, east, south or west, and turned with rotate
+    rotatable: bool = "false",
+    /// how it looks facing north when rotatable, unset fields are taken from its [visual_data]
+    north: VisualDeserData,
+    /// how it looks facing east when rotatable, unset fields are taken from its [visual_data]
+    east: VisualDeserData,
+    /// how it looks facing south when rotatable, unset fields are taken from its [visual_data]
+    south: VisualDeserData,
+    /// how it looks facing west when rotatable, unset fields are taken from its [visual_data]
+    west: VisualDeserData,
+    /// animations scripts can switch it to by name, unset fields are taken from its [visual_data]
+    animations: BTreeMap<String, VisualDeserData>
 }
 
 deserializable_module_data!{
     [has_visual]
 
-    struct GroundDeserialData
-    solid: bool,
-    world_gen_weight: f64
+    pub struct GroundDeserialData
+    /// the entry's name in an array of entries, e.g. [[tiles]], files use their file name
+    name: String,
+    /// if the player can't walk on it
+    solid: bool = "true",
+    /// how often world generation places it, 0 means never
+    world_gen_weight: f64 = "0.0"
 }
 
 deserializable_module_data!{
     [has_visual]
 
-    struct ItemDeserialData
+    pub struct ItemDeserialData
+    /// the entry's name in an array of entries, e.g. [[tiles]], files use their file name
+    name: String
 }
 
 deserializable_module_data!{
     [has_visual]
 
-    struct VisibleThingDeserialData
+    pub struct EntityDeserialData
+    /// the entry's name in an array of entries, e.g. [[tiles]], files use their file name
+    name: String
+}
 
-    type_identifier: String,
+deserializable_module_data!{
+    [has_visual]
+
+    pub struct VisibleThingDeserialData
+
+    /// the entry's name in an array of entries, e.g. [[tiles]], files use their file name
+    name: String,
+    /// which handler receives it, see Core.GameInfo.Things.registerHandler
+    [required] type_identifier: String,
+    /// data for the handler
     bytes: Vec<u8>
 }
 
 deserializable_module_data!{
-    struct ThingDeserialData
+    pub struct ThingDeserialData
 
-    type_identifier: String,
+    /// the entry's name in an array of entries, e.g. [[tiles]], files use their file name
+    name: String,
+    /// which handler receives it, see Core.GameInfo.Things.registerHandler
+    [required] type_identifier: String,
+    /// data for the handler
     bytes: Vec<u8>
 }
 
 deserializable_module_data!{
     [has_visual]
 
-    struct ByteStreamDeserialData
+    pub struct ByteStreamDeserialData
 
+    /// the entry's name in an array of entries, e.g. [[tiles]], files use their file name
+    name: String,
+    /// which handler receives it, see Core.GameInfo.ByteStreams.registerHandler
     type_identifier: String,
-    bytes: Vec<u8>
+    /// the data itself
+    [required] bytes: Vec<u8>
 }
 
 #[derive(Debug)]
@@ -97,7 +195,8 @@ pub struct MappedGameDatas {
     pub item_types: MappedGameData<ItemType>, 
     pub visible_thing_types: MappedGameData<VisibleThingType>, 
     pub thing_types: MappedGameData<ThingType>, 
-    pub byte_streams: MappedGameData<ByteStream>
+    pub byte_streams: MappedGameData<ByteStream>,
+    pub entity_types: MappedGameData<EntityType>,
 }
 
 #[derive(Debug)]
@@ -108,6 +207,7 @@ pub struct IdentifierMaps {
     pub visible_thing_types: BiMap<String, u16>,
     pub thing_types: BiMap<String, u16>,
     pub byte_streams: BiMap<String, u16>,
+    pub entity_types: BiMap<String, u16>,
 }
 
 #[derive(Debug)]
@@ -116,8 +216,43 @@ pub struct GameDataDump {
     pub identifiers: IdentifierMaps
 }
 
+impl GameDataDump {
+    /// every mapped type whose id doesn't match its key or its identifier, should always be empty
+    pub fn mismatched_ids(&self) -> Vec<String> {
+        let mut mismatched = Vec::new();
+        let (data, identifiers) = (&self.game_data, &self.identifiers);
+
+        mismatched_ids_in("tile", &data.tile_types, &identifiers.tile_types, |tile_type| tile_type.identifier, &mut mismatched);
+        mismatched_ids_in("ground", &data.ground_types, &identifiers.ground_types, |ground_type| ground_type.identifier, &mut mismatched);
+        mismatched_ids_in("item", &data.item_types, &identifiers.item_types, |item_type| item_type.identifier, &mut mismatched);
+        mismatched_ids_in("vis_thing", &data.visible_thing_types, &identifiers.visible_thing_types, |thing_type| thing_type.identifier, &mut mismatched);
+        mismatched_ids_in("thing", &data.thing_types, &identifiers.thing_types, |thing_type| thing_type.identifier, &mut mismatched);
+        mismatched_ids_in("byte_stream", &data.byte_streams, &identifiers.byte_streams, |byte_stream| byte_stream.identifier, &mut mismatched);
+        mismatched_ids_in("entity", &data.entity_types, &identifiers.entity_types, |entity_type| entity_type.identifier, &mut mismatched);
+
+        mismatched
+    }
+}
+
+fn mismatched_ids_in<T>(data_type: &str, types: &MappedGameData<T>, identifiers: &BiMap<String, u16>, id_of: impl Fn(&T) -> u16, mismatched: &mut Vec<String>) {
+    for (key, mapped_type) in types {
+        let id = id_of(mapped_type);
+        if id != *key {
+            mismatched.push(format!("{} stored under {} has id {}", data_type, key, id));
+        }
+        if !identifiers.contains_right(key) {
+            mismatched.push(format!("{} {} has no identifier", data_type, key));
+        }
+    }
+    for (identifier, key) in identifiers {
+        if !types.contains_key(key) {
+            mismatched.push(format!("{} '{}' points to {}, which isn't mapped", data_type, identifier, key));
+        }
+    }
+}
+
 // mapping time
-fn filtered_pre_map_to_map<'a>(filtered_map_dump: &'a mut PreMapDump<'a>, id_tracker: &mut IdTracker) -> GameDataDump {
+fn filtered_pre_map_to_map<'a>(filtered_map_dump: &'a mut PreMapDump<'a>, id_allocators: &mut IdAllocators, diagnostics: &mut Vec<ModuleLoadError>) -> GameDataDump {
     // primary types
     let mut tile_type_map: MappedGameData<TileType> = HashMap::new();
     let mut ground_type_map: MappedGameData<GroundType> = HashMap::new();
@@ -136,53 +271,69 @@ fn filtered_pre_map_to_map<'a>(filtered_map_dump: &'a mut PreMapDump<'a>, id_tra
     let mut thing_type_identifiers: BiMap<String, u16> = BiMap::new();
     let mut byte_stream_identifiers: BiMap<String, u16> = BiMap::new();
 
-    for i in (0..filtered_map_dump.len()).rev() {
-        let entry = &filtered_map_dump[i];
+    let mut entity_type_map: MappedGameData<EntityType> = HashMap::new();
+    let mut entity_type_identifiers: BiMap<String, u16> = BiMap::new();
+
+    let visual_data_index = index_visual_data(filtered_map_dump);
+
+    for entry in filtered_map_dump.iter().rev() {
 
         let ident = &entry.identifier;
 
         match ident.data_type.as_str() {
             "tile" => {
                 let downcast = entry.data.as_any().downcast_ref::<TileDeserialData>().unwrap();
-                let visual_deser = match find_visual_data(filtered_map_dump, &entry.identifier) {
-                    Some(vd) => vd,
-                    None => { 
-                        write_to_debug(format!("Failed to load {}!", entry.identifier.name));
-                        continue; 
-                    },
+                let visual_data = match visual_data_for(&visual_data_index, entry, diagnostics) {
+                    Some(visual_data) => visual_data,
+                    None => continue,
+                };
+                let rotatable = downcast.rotatable.unwrap_or(false);
+                let facing_animations = match facing_animations_for(&visual_data_index, entry, downcast, rotatable, diagnostics) {
+                    Some(facing_animations) => facing_animations,
+                    None => continue,
+                };
+                let animations = match named_animations_for(&visual_data_index, entry, downcast, diagnostics) {
+                    Some(animations) => animations,
+                    None => continue,
                 };
-                let visual_data = text_and_color_from_deser(visual_deser);
 
+                let id = match allocate_id(&mut id_allocators.tile_types, entry, diagnostics) {
+                    Some(id) => id,
+                    None => continue,
+                };
                 tile_type_map.insert(
-                    id_tracker.0, 
+                    id,
                     TileType {
-                        identifier: id_tracker.0,
+                        identifier: id,
                         name: visual_data.2,
                         text_display: visual_data.0,
                         color_display: visual_data.1,
                         solid: downcast.solid.unwrap_or(true),
                         world_gen_weight: downcast.world_gen_weight.unwrap_or(0.0),
+                        rotatable,
+                        facing_animations,
+                        animations,
                     }
                 );
                 
-                tile_type_identifiers.insert(format!("{}:{}", ident.source, ident.name), id_tracker.0);
-                id_tracker.0 += 1;
+                tile_type_identifiers.insert(format!("{}:{}", ident.source, ident.name), id);
             },
             "ground" => {
                 let downcast = entry.data.as_any().downcast_ref::<GroundDeserialData>().unwrap();
-                let visual_deser = match find_visual_data(filtered_map_dump, &entry.identifier) {
-                    Some(vd) => vd,
-                    None => { 
-                        println!("Failed to load {}!", entry.identifier.name);
-                        continue; 
-                    },
+                let visual_data = match visual_data_for(&visual_data_index, entry, diagnostics) {
+                    Some(visual_data) => visual_data,
+                    None => continue,
                 };
-                let visual_data = text_and_color_from_deser(visual_deser);
 
+                let id = match allocate_id(&mut id_allocators.ground_types, entry, diagnostics) {
+                    Some(id) => id,
+                    None => continue,
+                };
                 ground_type_map.insert(
-                    id_tracker.1, 
+                    id,
                     GroundType {
-                        identifier: id_tracker.1,
+                        identifier: id,
+                        name: visual_data.2,
                         text_display: visual_data.0,
                         color_display: visual_data.1,
                         solid: downcast.solid.unwrap_or(true),
@@ -190,86 +341,131 @@ fn filtered_pre_map_to_map<'a>(filtered_map_dump: &'a mut PreMapDump<'a>, id_tra
                     }
                 );
 
-                ground_type_identifiers.insert(format!("{}:{}", ident.source, ident.name), id_tracker.1);
-                id_tracker.1 += 1;
+                ground_type_identifiers.insert(format!("{}:{}", ident.source, ident.name), id);
             },
             "item" => {
-                let visual_deser = match find_visual_data(filtered_map_dump, &entry.identifier) {
-                    Some(vd) => vd,
-                    None => { 
-                        println!("Failed to load {}!", entry.identifier.name);
-                        continue; 
-                    },
+                let visual_data = match visual_data_for(&visual_data_index, entry, diagnostics) {
+                    Some(visual_data) => visual_data,
+                    None => continue,
                 };
-                let visual_data = text_and_color_from_deser(visual_deser);
 
+                let id = match allocate_id(&mut id_allocators.item_types, entry, diagnostics) {
+                    Some(id) => id,
+                    None => continue,
+                };
                 item_type_map.insert(
-                    id_tracker.2, 
+                    id,
                     ItemType {
-                        identifier: id_tracker.1,
+                        identifier: id,
+                        name: visual_data.2,
+                        text_display: visual_data.0,
+                        color_display: visual_data.1,
+                    }
+                );
+
+                item_type_identifiers.insert(format!("{}:{}", ident.source, ident.name), id);
+            },
+            "entity" => {
+                let visual_data = match visual_data_for(&visual_data_index, entry, diagnostics) {
+                    Some(visual_data) => visual_data,
+                    None => continue,
+                };
+
+                let id = match allocate_id(&mut id_allocators.entity_types, entry, diagnostics) {
+                    Some(id) => id,
+                    None => continue,
+                };
+                entity_type_map.insert(
+                    id,
+                    EntityType {
+                        identifier: id,
                         text_display: visual_data.0,
                         color_display: visual_data.1,
                     }
                 );
 
-                item_type_identifiers.insert(format!("{}:{}", ident.source, ident.name), id_tracker.2);
-                id_tracker.2 += 1;
+                entity_type_identifiers.insert(format!("{}:{}", ident.source, ident.name), id);
             },
             "vis_thing" => {
                 let downcast = entry.data.as_any().downcast_ref::<VisibleThingDeserialData>().unwrap();
-                let visual_deser = match find_visual_data(filtered_map_dump, &entry.identifier) {
-                    Some(vd) => vd,
-                    None => { 
-                        println!("Failed to load {}!", entry.identifier.name);
-                        continue; 
+                let type_identifier = match &downcast.type_identifier {
+                    Some(type_identifier) => type_identifier.clone(),
+                    None => {
+                        diagnostics.push(missing_field(entry, "type_identifier"));
+                        continue;
                     },
                 };
-                let visual_data = text_and_color_from_deser(visual_deser);
+                let visual_data = match visual_data_for(&visual_data_index, entry, diagnostics) {
+                    Some(visual_data) => visual_data,
+                    None => continue,
+                };
 
+                let id = match allocate_id(&mut id_allocators.visible_thing_types, entry, diagnostics) {
+                    Some(id) => id,
+                    None => continue,
+                };
                 visible_thing_type_map.insert(
-                    id_tracker.3, 
+                    id,
                     VisibleThingType {
-                        identifier: id_tracker.1,
-                        type_identifier: {
-                            downcast.type_identifier.as_ref().unwrap().clone()
-                        },
+                        identifier: id,
+                        type_identifier,
+                        bytes: downcast.bytes.clone().unwrap_or_default(),
                         text_display: visual_data.0,
                         color_display: visual_data.1,
                     }
                 );
 
-                visible_thing_type_identifiers.insert(format!("{}:{}", ident.source, ident.name), id_tracker.3);
-                id_tracker.3 += 1;
+                visible_thing_type_identifiers.insert(format!("{}:{}", ident.source, ident.name), id);
             },
             "thing" => {
                 let downcast = entry.data.as_any().downcast_ref::<ThingDeserialData>().unwrap();
+                let type_identifier = match &downcast.type_identifier {
+                    Some(type_identifier) => type_identifier.clone(),
+                    None => {
+                        diagnostics.push(missing_field(entry, "type_identifier"));
+                        continue;
+                    },
+                };
 
+                let id = match allocate_id(&mut id_allocators.thing_types, entry, diagnostics) {
+                    Some(id) => id,
+                    None => continue,
+                };
                 thing_type_map.insert(
-                    id_tracker.4, 
+                    id,
                     ThingType {
-                        identifier: id_tracker.1,
-                        type_identifier: {
-                            downcast.type_identifier.as_ref().unwrap().clone()
-                        },
+                        identifier: id,
+                        type_identifier,
+                        bytes: downcast.bytes.clone().unwrap_or_default(),
                     }
                 );
 
-                thing_type_identifiers.insert(format!("{}:{}", ident.source, ident.name), id_tracker.4);
-                id_tracker.4 += 1;
+                thing_type_identifiers.insert(format!("{}:{}", ident.source, ident.name), id);
             },
             "byte_stream" => {
                 let downcast = entry.data.as_any().downcast_ref::<ByteStreamDeserialData>().unwrap();
+                let bytes = match &downcast.bytes {
+                    Some(bytes) => bytes.clone(),
+                    None => {
+                        diagnostics.push(missing_field(entry, "bytes"));
+                        continue;
+                    },
+                };
 
+                let id = match allocate_id(&mut id_allocators.byte_streams, entry, diagnostics) {
+                    Some(id) => id,
+                    None => continue,
+                };
                 byte_stream_map.insert(
-                    id_tracker.5, 
+                    id,
                     ByteStream {
-                        identifier: id_tracker.1,
-                        bytes: downcast.bytes.as_ref().unwrap().clone(),
+                        identifier: id,
+                        type_identifier: downcast.type_identifier.clone(),
+                        bytes,
                     }
                 );
 
-                byte_stream_identifiers.insert(format!("{}:{}", ident.source, ident.name), id_tracker.5);
-                id_tracker.5 += 1;
+                byte_stream_identifiers.insert(format!("{}:{}", ident.source, ident.name), id);
             },
             _ => {}
         }
@@ -283,6 +479,7 @@ fn filtered_pre_map_to_map<'a>(filtered_map_dump: &'a mut PreMapDump<'a>, id_tra
             visible_thing_types: visible_thing_type_map,
             thing_types: thing_type_map,
             byte_streams: byte_stream_map,
+            entity_types: entity_type_map,
         },
         identifiers: IdentifierMaps {
             tile_types: tile_type_identifiers,
@@ -291,28 +488,181 @@ fn filtered_pre_map_to_map<'a>(filtered_map_dump: &'a mut PreMapDump<'a>, id_tra
             visible_thing_types: visible_thing_type_identifiers,
             thing_types: thing_type_identifiers,
             byte_streams: byte_stream_identifiers,
+            entity_types: entity_type_identifiers,
         },
     }
 }
 
-fn find_visual_data<'a>(filtered_map_dump: &'a PreMapDump<'a>, identifier: &PreMapIdentifier) -> Option<&'a VisualDeserData> {
-    for i in (0..filtered_map_dump.len()).rev() {
-        let ident = &filtered_map_dump[i].identifier;
-        if ident.data_type == "vis_data" && ident.source == identifier.source && ident.name == identifier.name  {
-            return filtered_map_dump[i].data.as_any().downcast_ref::<VisualDeserData>();
+/// every top level [visual_data] by (source, name)
+fn index_visual_data<'a>(filtered_map_dump: &'a PreMapDump<'a>) -> HashMap<(&'a str, &'a str), &'a VisualDeserData> {
+    filtered_map_dump.iter()
+        .filter(|entry| entry.identifier.data_type == "vis_data")
+        .filter_map(|entry| {
+            let visual_data = entry.data.as_any().downcast_ref::<VisualDeserData>()?;
+            Some(((entry.identifier.source.as_str(), entry.identifier.name.as_str()), visual_data))
+        })
+        .collect()
+}
+
+/// finds and converts the visual data belonging to entry, reporting why it couldn't if it can't
+fn visual_data_for(visual_data_index: &HashMap<(&str, &str), &VisualDeserData>, entry: &UnmappedData, diagnostics: &mut Vec<ModuleLoadError>) -> Option<(TextDisplay, ColorDisplay, String)> {
+    let visual_deser = visual_deser_for(visual_data_index, entry, diagnostics)?;
+
+    // still loaded, just without animating
+    let ident = &entry.identifier;
+    if ident.data_type != "tile" && (visual_deser.frames.is_some() || visual_deser.frame_duration.is_some()) {
+        diagnostics.push(ModuleLoadError::new(
+            entry.path.as_deref(),
+            ModuleLoadErrorKind::InvalidVisualData {
+                name: ident.name.clone(),
+                reason: format!("only tiles animate, frames and frame_duration are ignored for a {}", ident.data_type),
+            }
+        ));
+    }
+
+    convert_visual_data(entry, visual_deser, diagnostics)
+}
+
+/// the next id for entry, reporting it if there are none left
+fn allocate_id(id_allocator: &mut IdAllocator, entry: &UnmappedData, diagnostics: &mut Vec<ModuleLoadError>) -> Option<u16> {
+    let id = id_allocator.allocate();
+    if id.is_none() {
+        let ident = &entry.identifier;
+        diagnostics.push(ModuleLoadError::new(
+            entry.path.as_deref(),
+            ModuleLoadErrorKind::OutOfIds { data_type: ident.data_type.clone(), identifier: format!("{}:{}", ident.source, ident.name) }
+        ));
+    }
+    id
+}
+
+/// the embedded visual data of entry, or the [visual_data] of its file
+fn visual_deser_for<'a>(visual_data_index: &HashMap<(&str, &str), &'a VisualDeserData>, entry: &'a UnmappedData, diagnostics: &mut Vec<ModuleLoadError>) -> Option<&'a VisualDeserData> {
+    let ident = &entry.identifier;
+    let embedded_visual_data = entry.data.embedded_visual_data();
+    let indexed_visual_data = visual_data_index.get(&(ident.source.as_str(), ident.name.as_str())).copied();
+    match embedded_visual_data.or(indexed_visual_data) {
+        Some(visual_deser) => Some(visual_deser),
+        None => {
+            diagnostics.push(ModuleLoadError::new(
+                entry.path.as_deref(),
+                ModuleLoadErrorKind::MissingVisualData { data_type: ident.data_type.clone(), name: ident.name.clone() }
+            ));
+            None
+        },
+    }
+}
+
+/// how a tile looks facing each Direction
+/// directions without their own visual data, and every direction of tiles which aren't rotatable, use the tile's
+fn facing_animations_for(visual_data_index: &HashMap<(&str, &str), &VisualDeserData>, entry: &UnmappedData, tile: &TileDeserialData, rotatable: bool, diagnostics: &mut Vec<ModuleLoadError>) -> Option<[Animation; 4]> {
+    let base_deser = visual_deser_for(visual_data_index, entry, diagnostics)?;
+    let base_animation = convert_animation(entry, base_deser, diagnostics)?;
+    let mut facing_animations = [base_animation.clone(), base_animation.clone(), base_animation.clone(), base_animation];
+    if !rotatable {
+        return Some(facing_animations);
+    }
+
+    for (facing_animation, facing_deser) in facing_animations.iter_mut().zip([&tile.north, &tile.east, &tile.south, &tile.west]) {
+        if let Some(facing_deser) = facing_deser {
+            let mut merged = facing_deser.clone();
+            merged.inherit_from(base_deser);
+            *facing_animation = convert_animation(entry, &merged, diagnostics)?;
         }
     }
-    None
+    Some(facing_animations)
 }
 
-fn text_and_color_from_deser(visual_deser: &VisualDeserData) -> (TextDisplay, ColorDisplay, String) {
+/// a tile's named animations, sorted by name
+fn named_animations_for(visual_data_index: &HashMap<(&str, &str), &VisualDeserData>, entry: &UnmappedData, tile: &TileDeserialData, diagnostics: &mut Vec<ModuleLoadError>) -> Option<Vec<(String, Animation)>> {
+    let animation_desers = match &tile.animations {
+        Some(animation_desers) => animation_desers,
+        None => return Some(Vec::new()),
+    };
+    let base_deser = visual_deser_for(visual_data_index, entry, diagnostics)?;
+
+    let mut animations = Vec::with_capacity(animation_desers.len());
+    for (name, animation_deser) in animation_desers {
+        let mut merged = animation_deser.clone();
+        merged.inherit_from(base_deser);
+        animations.push((name.clone(), convert_animation(entry, &merged, diagnostics)?));
+    }
+    Some(animations)
+}
+
+/// a single frame, or every frame in visual_deser.frames with unset fields taken from visual_deser
+fn convert_animation(entry: &UnmappedData, visual_deser: &VisualDeserData, diagnostics: &mut Vec<ModuleLoadError>) -> Option<Animation> {
+    let invalid = |reason: &str| ModuleLoadError::new(
+        entry.path.as_deref(),
+        ModuleLoadErrorKind::InvalidVisualData { name: entry.identifier.name.clone(), reason: reason.to_owned() }
+    );
+
+    let frame_desers = match &visual_deser.frames {
+        Some(frame_desers) if !frame_desers.is_empty() => frame_desers,
+        _ => {
+            let (text_display, color_display, _) = convert_visual_data(entry, visual_deser, diagnostics)?;
+            return Some(Animation::still(text_display, color_display));
+        },
+    };
+
+    let frame_duration = visual_deser.frame_duration.unwrap_or(1);
+    if frame_duration == 0 {
+        diagnostics.push(invalid("frame_duration must be at least 1"));
+        return None;
+    }
+
+    let mut frames = Vec::with_capacity(frame_desers.len());
+    for frame_deser in frame_desers {
+        if frame_deser.frames.is_some() {
+            diagnostics.push(invalid("frames can't have frames of their own"));
+            return None;
+        }
+        let mut merged = frame_deser.clone();
+        merged.inherit_from(visual_deser);
+        let (text_display, color_display, _) = convert_visual_data(entry, &merged, diagnostics)?;
+        frames.push((text_display, color_display));
+    }
+
+    Some(Animation { frames, frame_duration })
+}
+
+fn convert_visual_data(entry: &UnmappedData, visual_deser: &VisualDeserData, diagnostics: &mut Vec<ModuleLoadError>) -> Option<(TextDisplay, ColorDisplay, String)> {
+    let ident = &entry.identifier;
+    match text_and_color_from_deser(visual_deser) {
+        Ok(visual_data) => Some(visual_data),
+        Err(reason) => {
+            diagnostics.push(ModuleLoadError::new(
+                entry.path.as_deref(),
+                ModuleLoadErrorKind::InvalidVisualData { name: ident.name.clone(), reason }
+            ));
+            None
+        },
+    }
+}
+
+fn missing_field(entry: &UnmappedData, field: &str) -> ModuleLoadError {
+    ModuleLoadError::new(
+        entry.path.as_deref(),
+        ModuleLoadErrorKind::MissingField {
+            data_type: entry.identifier.data_type.clone(),
+            name: entry.identifier.name.clone(),
+            field: field.to_owned(),
+        }
+    )
+}
+
+fn first_char(field: &str, text: &str) -> Result<char, String> {
+    text.chars().next().ok_or_else(|| format!("{} is empty", field))
+}
+
+fn text_and_color_from_deser(visual_deser: &VisualDeserData) -> Result<(TextDisplay, ColorDisplay, String), String> {
     let mut text = TextDisplayBuilder::new();
 
     if let Some(cl) = &visual_deser.character_left {
-        text.character_left(cl.chars().nth(0).unwrap());
+        text.character_left(first_char("character_left", cl)?);
     }
     if let Some(cr) = &visual_deser.character_right {
-        text.character_right(cr.chars().nth(0).unwrap());
+        text.character_right(first_char("character_right", cr)?);
     }
 
     let text = text.finalize();
@@ -339,29 +689,48 @@ fn text_and_color_from_deser(visual_deser: &VisualDeserData) -> (TextDisplay, Co
         None => String::from(""),
     };
 
-    (text, color, name)
+    Ok((text, color, name))
 }
 
 // pre map
-fn filter_pre_map_dump(pre_map_dump: & mut PreMapDump) {
-    for i in (0..pre_map_dump.len()).rev() {
-        for j in (0..pre_map_dump.len()).rev() {
-            if i == j { continue; }
-
-            let current = &pre_map_dump[i];
-            let other = &pre_map_dump[j];
-
-            let cur_ident = &current.identifier;
-            let oth_ident = &other.identifier;
-
-            if cur_ident.source == oth_ident.source && cur_ident.data_type == oth_ident.data_type && cur_ident.name == oth_ident.name {
-                if cur_ident.priority < oth_ident.priority {
-                    pre_map_dump[i] = other.clone();
+/// keeps only the highest priority entry for each (source, data type, name)
+fn filter_pre_map_dump(pre_map_dump: & mut PreMapDump, diagnostics: &mut Vec<ModuleLoadError>) {
+    let mut winners: HashMap<(&str, &str, &str), usize> = HashMap::with_capacity(pre_map_dump.len());
+
+    for (i, current) in pre_map_dump.iter().enumerate() {
+        let cur_ident = &current.identifier;
+        let key = (cur_ident.source.as_str(), cur_ident.data_type.as_str(), cur_ident.name.as_str());
+
+        match winners.get(&key) {
+            Some(&winner) => {
+                let other = &pre_map_dump[winner];
+                let oth_ident = &other.identifier;
+
+                if cur_ident.priority == oth_ident.priority {
+                    diagnostics.push(ModuleLoadError::new(
+                        current.path.as_deref(),
+                        ModuleLoadErrorKind::DuplicateIdentifier {
+                            data_type: cur_ident.data_type.clone(),
+                            identifier: format!("{}:{}", cur_ident.source, cur_ident.name),
+                            priority: cur_ident.priority,
+                            other: other.path.clone(),
+                        }
+                    ));
                 }
-                pre_map_dump.remove(j);
-            }
+                if cur_ident.priority > oth_ident.priority {
+                    winners.insert(key, i);
+                }
+            },
+            None => { winners.insert(key, i); },
         }
     }
+
+    let mut keep = vec![false; pre_map_dump.len()];
+    for i in winners.into_values() {
+        keep[i] = true;
+    }
+    let mut keep = keep.into_iter();
+    pre_map_dump.retain(|_| keep.next().unwrap());
 }
 
 // pre filter
@@ -376,23 +745,55 @@ struct PreMapIdentifier {
 trait AsAny {
     fn as_any(&self) -> &dyn Any;
 }
-trait Deserialization: std::fmt::Debug + AsAny {}
+trait Deserialization: std::fmt::Debug + AsAny {
+    /// visual_data written inside the entry itself, e.g. [tiles.visual_data]
+    fn embedded_visual_data(&self) -> Option<&VisualDeserData>;
+    fn present_fields(&self) -> Vec<&'static str>;
+}
 
 #[derive(Clone, Debug)]
 pub struct UnmappedData<'a> {
     identifier: PreMapIdentifier,
+    /// the file the data came from, None if it came from lua
+    path: Option<PathBuf>,
     data: Box<&'a dyn Deserialization>
 }
 
-pub fn map_deserialized_dump<'a>(pre_map_dump: &'a mut PreMapDump<'a>, deserial_dump: &'a DeserializationDump, id_tracker: &mut IdTracker) -> GameDataDump {
+pub fn map_deserialized_dump<'a>(pre_map_dump: &'a mut PreMapDump<'a>, deserial_dump: &'a DeserializationDump, id_allocators: &mut IdAllocators, diagnostics: &mut Vec<ModuleLoadError>) -> GameDataDump {
     for (module_name, module_contents) in deserial_dump {
         for (file_name, file_data) in module_contents {
+            if file_data.template == Some(true) || file_data.patch.is_some() { continue; }
+
             let priority = file_data.priority.unwrap_or(0);
 
-            let file_contents = get_in_file(file_data);
+            if let Some(explicit_source) = &file_data.source {
+                if !deserial_dump.contains_key(explicit_source) {
+                    diagnostics.push(ModuleLoadError::new(
+                        file_data.path.as_deref(),
+                        ModuleLoadErrorKind::UnknownSource(explicit_source.clone())
+                    ));
+                }
+            }
+
+            let file_contents = get_in_file(file_name, file_data, diagnostics);
+
+            for (data_type, name, data) in file_contents {
+                {
+                    let present_fields = data.present_fields();
+                    let schema_fields = schema_for(&data_type).map(|schema| schema.fields).unwrap_or(&[]);
+                    let missing_fields: Vec<&FieldSchema> = schema_fields.iter()
+                        .filter(|field| field.required && !present_fields.contains(&field.name))
+                        .collect();
+                    if !missing_fields.is_empty() {
+                        for field in missing_fields {
+                            diagnostics.push(ModuleLoadError::new(
+                                file_data.path.as_deref(),
+                                ModuleLoadErrorKind::MissingField { data_type: data_type.clone(), name: name.clone(), field: field.name.to_owned() }
+                            ));
+                        }
+                        continue;
+                    }
 
-            for file_content in file_contents {
-                if let Some((data_type, data)) = file_content {
                     let pre_map_ident = PreMapIdentifier {
                         source: {
                             match &file_data.source {
@@ -402,11 +803,12 @@ pub fn map_deserialized_dump<'a>(pre_map_dump: &'a mut PreMapDump<'a>, deserial_
                         },
                         priority,
                         data_type: data_type.to_owned(),
-                        name: file_name.clone(),
+                        name,
                     };
         
                     let pre_map_data = UnmappedData {
                         identifier: pre_map_ident,
+                        path: file_data.path.clone(),
                         data,
                     };
 
@@ -416,59 +818,495 @@ pub fn map_deserialized_dump<'a>(pre_map_dump: &'a mut PreMapDump<'a>, deserial_
         }
     }
 
-    filter_pre_map_dump(pre_map_dump);
-    filtered_pre_map_to_map(pre_map_dump, id_tracker)
+    filter_pre_map_dump(pre_map_dump, diagnostics);
+    filtered_pre_map_to_map(pre_map_dump, id_allocators, diagnostics)
 }
 
-fn get_in_file(file_data: &ModuleDeserialization) -> [Option<(String, Box<&dyn Deserialization>)>; 7] {
-    let mut file_contents: [Option<(String, Box<&dyn Deserialization>)>; 7] = [None, None, None, None, None, None, None];
-    // prefixed cause the read in macro wasn't counting as a read
-    let mut _last = 0;
+/// every entry in a file as (data type, name, data)
+/// single entries are named after the file, entries in arrays by their name field
+fn get_in_file<'a>(file_name: &str, file_data: &'a ModuleDeserialization, diagnostics: &mut Vec<ModuleLoadError>) -> Vec<(String, String, Box<&'a dyn Deserialization>)> {
+    let mut file_contents: Vec<(String, String, Box<&dyn Deserialization>)> = Vec::new();
 
     macro_rules! add_to_file_contents {
         (
             $thing:expr; $type:expr
         ) => {
             if let Some(x) = &$thing {
-                file_contents[_last] = Some((String::from($type), Box::new(x)));
+                file_contents.push((String::from($type), file_name.to_owned(), Box::new(x)));
+            }
+        };
+        (
+            $thing:expr; $type:expr; $key:expr
+        ) => {
+            if let Some(x) = &$thing {
+                if x.name.is_some() {
+                    diagnostics.push(ModuleLoadError::new(
+                        file_data.path.as_deref(),
+                        ModuleLoadErrorKind::NameOutsideArray { key: String::from($key) }
+                    ));
+                }
+                file_contents.push((String::from($type), file_name.to_owned(), Box::new(x)));
             }
-            _last += 1;
         };
     }
 
-    
-    add_to_file_contents!(file_data.tile; "tile");
-    add_to_file_contents!(file_data.ground; "ground");
-    add_to_file_contents!(file_data.item; "item");
-    add_to_file_contents!(file_data.visible_thing; "vis_thing");
-    add_to_file_contents!(file_data.thing; "thing");
-    add_to_file_contents!(file_data.byte_stream; "byte_stream");
+    macro_rules! add_entries_to_file_contents {
+        (
+            $things:expr; $type:expr; $key:expr
+        ) => {
+            for (i, x) in $things.iter().flatten().enumerate() {
+                match &x.name {
+                    Some(name) => file_contents.push((String::from($type), name.clone(), Box::new(x))),
+                    None => diagnostics.push(ModuleLoadError::new(
+                        file_data.path.as_deref(),
+                        ModuleLoadErrorKind::MissingField { data_type: String::from($type), name: format!("{}[{}]", $key, i), field: String::from("name") }
+                    )),
+                }
+            }
+        };
+    }
+
+    add_to_file_contents!(file_data.tile; "tile"; "tile");
+    add_to_file_contents!(file_data.ground; "ground"; "ground");
+    add_to_file_contents!(file_data.item; "item"; "item");
+    add_to_file_contents!(file_data.entity; "entity"; "entity");
+    add_to_file_contents!(file_data.visible_thing; "vis_thing"; "visible_thing");
+    add_to_file_contents!(file_data.thing; "thing"; "thing");
+    add_to_file_contents!(file_data.byte_stream; "byte_stream"; "byte_stream");
     add_to_file_contents!(file_data.visual_data; "vis_data");
 
+    add_entries_to_file_contents!(file_data.tiles; "tile"; "tiles");
+    add_entries_to_file_contents!(file_data.grounds; "ground"; "grounds");
+    add_entries_to_file_contents!(file_data.items; "item"; "items");
+    add_entries_to_file_contents!(file_data.entities; "entity"; "entities");
+    add_entries_to_file_contents!(file_data.visible_things; "vis_thing"; "visible_things");
+    add_entries_to_file_contents!(file_data.things; "thing"; "things");
+    add_entries_to_file_contents!(file_data.byte_streams; "byte_stream"; "byte_streams");
+
     file_contents
 }
 
 
 
+// inheritance
+/// Filling in what isn't set from a parent definition
+trait Inherit {
+    fn inherit_from(&mut self, parent: &Self);
+}
+
+fn inherit_table<T: Inherit + Clone>(child: &mut Option<T>, parent: &Option<T>) {
+    match (child.as_mut(), parent) {
+        (Some(child), Some(parent)) => child.inherit_from(parent),
+        (None, Some(parent)) => *child = Some(parent.clone()),
+        _ => {},
+    }
+}
+
+impl Inherit for ModuleDeserialization {
+    /// source, priority and arrays of entries belong to the file and aren't inherited
+    fn inherit_from(&mut self, parent: &Self) {
+        inherit_table(&mut self.tile, &parent.tile);
+        inherit_table(&mut self.ground, &parent.ground);
+        inherit_table(&mut self.item, &parent.item);
+        inherit_table(&mut self.entity, &parent.entity);
+        inherit_table(&mut self.visible_thing, &parent.visible_thing);
+        inherit_table(&mut self.thing, &parent.thing);
+        inherit_table(&mut self.byte_stream, &parent.byte_stream);
+        inherit_table(&mut self.visual_data, &parent.visual_data);
+    }
+}
+
+impl ModuleDeserialization {
+    /// the fields this file sets, as table.field
+    fn set_fields(&self) -> Vec<String> {
+        let mut set_fields = Vec::new();
+
+        macro_rules! add_set_fields {
+            (
+                $table:expr; $key:expr
+            ) => {
+                if let Some(table) = &$table {
+                    for field in table.present_fields() {
+                        set_fields.push(format!("{}.{}", $key, field));
+                    }
+                    if let Some(visual_data) = table.embedded_visual_data() {
+                        for field in visual_data.present_fields() {
+                            set_fields.push(format!("{}.visual_data.{}", $key, field));
+                        }
+                    }
+                }
+            };
+        }
+
+        add_set_fields!(self.tile; "tile");
+        add_set_fields!(self.ground; "ground");
+        add_set_fields!(self.item; "item");
+        add_set_fields!(self.entity; "entity");
+        add_set_fields!(self.visible_thing; "visible_thing");
+        add_set_fields!(self.thing; "thing");
+        add_set_fields!(self.byte_stream; "byte_stream");
+        add_set_fields!(self.visual_data; "visual_data");
+
+        set_fields
+    }
+
+    /// overwrites every table field patch sets, keeping the rest
+    fn apply_patch(&mut self, patch: &Self) {
+        patch_table(&mut self.tile, &patch.tile);
+        patch_table(&mut self.ground, &patch.ground);
+        patch_table(&mut self.item, &patch.item);
+        patch_table(&mut self.entity, &patch.entity);
+        patch_table(&mut self.visible_thing, &patch.visible_thing);
+        patch_table(&mut self.thing, &patch.thing);
+        patch_table(&mut self.byte_stream, &patch.byte_stream);
+        patch_table(&mut self.visual_data, &patch.visual_data);
+    }
+
+    /// every named entry in the file's arrays, with where it is
+    fn array_entries(&self) -> Vec<(EntryIndex, String)> {
+        let mut entries = Vec::new();
+
+        macro_rules! add_array_entries {
+            (
+                $array:ident
+            ) => {
+                for (i, entry) in self.$array.iter().flatten().enumerate() {
+                    if let Some(name) = &entry.name {
+                        entries.push(((stringify!($array), i), name.clone()));
+                    }
+                }
+            };
+        }
+
+        add_array_entries!(tiles);
+        add_array_entries!(grounds);
+        add_array_entries!(items);
+        add_array_entries!(entities);
+        add_array_entries!(visible_things);
+        add_array_entries!(things);
+        add_array_entries!(byte_streams);
+
+        entries
+    }
+
+    /// one definition in the file, laid out like a file of its own so it can be extended and patched the same way
+    /// an entry in an array goes in the matching single table, with its visual_data as the file's
+    fn definition(&self, entry: Option<EntryIndex>) -> Self {
+        let (array, i) = match entry {
+            Some(entry) => entry,
+            None => return self.clone(),
+        };
+        let mut definition = Self::default();
+
+        macro_rules! take_entry {
+            (
+                $array:ident; $table:ident
+            ) => {
+                if array == stringify!($array) {
+                    let mut table = self.$array.as_ref().unwrap()[i].clone();
+                    // only entries in arrays have names
+                    table.name = None;
+                    definition.$table = Some(table);
+                }
+            };
+            (
+                $array:ident; $table:ident; has_visual
+            ) => {
+                if array == stringify!($array) {
+                    let mut table = self.$array.as_ref().unwrap()[i].clone();
+                    table.name = None;
+                    definition.visual_data = table.visual_data.take();
+                    definition.$table = Some(table);
+                }
+            };
+        }
+
+        take_entry!(tiles; tile; has_visual);
+        take_entry!(grounds; ground; has_visual);
+        take_entry!(items; item; has_visual);
+        take_entry!(entities; entity; has_visual);
+        take_entry!(visible_things; visible_thing; has_visual);
+        take_entry!(things; thing);
+        take_entry!(byte_streams; byte_stream; has_visual);
+
+        definition
+    }
+
+    /// puts a definition taken out with definition back
+    fn set_definition(&mut self, entry: Option<EntryIndex>, mut definition: Self) {
+        let (array, i) = match entry {
+            Some(entry) => entry,
+            None => {
+                *self = definition;
+                return;
+            },
+        };
+
+        macro_rules! put_entry {
+            (
+                $array:ident; $table:ident
+            ) => {
+                if array == stringify!($array) {
+                    let entry = &mut self.$array.as_mut().unwrap()[i];
+                    let mut table = definition.$table.take().unwrap();
+                    table.name = entry.name.take();
+                    *entry = table;
+                }
+            };
+            (
+                $array:ident; $table:ident; has_visual
+            ) => {
+                if array == stringify!($array) {
+                    let entry = &mut self.$array.as_mut().unwrap()[i];
+                    let mut table = definition.$table.take().unwrap();
+                    table.name = entry.name.take();
+                    table.visual_data = definition.visual_data.take();
+                    *entry = table;
+                }
+            };
+        }
+
+        put_entry!(tiles; tile; has_visual);
+        put_entry!(grounds; ground; has_visual);
+        put_entry!(items; item; has_visual);
+        put_entry!(entities; entity; has_visual);
+        put_entry!(visible_things; visible_thing; has_visual);
+        put_entry!(things; thing);
+        put_entry!(byte_streams; byte_stream; has_visual);
+    }
+}
+
+fn patch_table<T: Inherit + Clone>(target: &mut Option<T>, patch: &Option<T>) {
+    if let Some(patch) = patch {
+        let mut patched = patch.clone();
+        if let Some(target) = target.as_ref() {
+            patched.inherit_from(target);
+        }
+        *target = Some(patched);
+    }
+}
+
+// patching
+/// deep merges every patch = "source:name" file into its target
+/// patches apply in order of priority, then module name, then file name, so later ones win
+fn apply_patches(deserial_dump: &mut DeserializationDump, diagnostics: &mut Vec<ModuleLoadError>) {
+    let definitions = highest_priority_definitions(deserial_dump);
+
+    let mut patches: Vec<(u8, DumpIndex)> = deserial_dump.iter()
+        .flat_map(|(module_name, data_stack)| {
+            data_stack.iter().enumerate()
+                .filter(|(_, (_, file_data))| file_data.patch.is_some())
+                .map(|(i, (_, file_data))| (file_data.priority.unwrap_or(0), (module_name.clone(), i, None)))
+        })
+        .collect();
+    patches.sort_by(|(priority, index), (other_priority, other_index)| {
+        let file_name = &deserial_dump[&index.0][index.1].0;
+        let other_file_name = &deserial_dump[&other_index.0][other_index.1].0;
+        (priority, &index.0, file_name).cmp(&(other_priority, &other_index.0, other_file_name))
+    });
+
+    // which module each field of a patched entry came from
+    let mut provenance: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+
+    for (_, patch_index) in patches {
+        let patch = deserial_dump[&patch_index.0][patch_index.1].1.clone();
+        let target_identifier = patch.patch.clone().unwrap();
+
+        let target_index = match definitions.get(&target_identifier) {
+            Some((_, target_index)) => target_index.clone(),
+            None => {
+                diagnostics.push(ModuleLoadError::new(patch.path.as_deref(), ModuleLoadErrorKind::MissingPatchTarget(target_identifier)));
+                continue;
+            },
+        };
+
+        let target_file = &mut deserial_dump.get_mut(&target_index.0).unwrap()[target_index.1].1;
+        let mut target = target_file.definition(target_index.2);
+        let fields = provenance.entry(target_identifier).or_insert_with(|| {
+            target.set_fields().into_iter().map(|field| (field, target_index.0.clone())).collect()
+        });
+        for field in patch.set_fields() {
+            fields.insert(field, patch_index.0.clone());
+        }
+
+        target.apply_patch(&patch);
+        target_file.set_definition(target_index.2, target);
+    }
+
+    for (identifier, fields) in &provenance {
+        let listing: Vec<String> = fields.iter().map(|(field, module_name)| format!("  {} from {}", field, module_name)).collect();
+        log_debug!("patched {}:\n{}", identifier, listing.join("\n"));
+    }
+}
+
+/// (module name, index in the module's data stack, entry)
+/// the entry is None for the file's own tables
+type DumpIndex = (String, usize, Option<EntryIndex>);
+/// (array key, index in the array) of an entry in a file
+type EntryIndex = (&'static str, usize);
+
+/// source:name, the same identifier get_in_file gives
+fn dump_identifier(deserial_dump: &DeserializationDump, index: &DumpIndex) -> String {
+    let (file_name, file_data) = &deserial_dump[&index.0][index.1];
+    let name = match index.2 {
+        Some(entry) => file_data.array_entries().into_iter().find(|(other, _)| *other == entry).unwrap().1,
+        None => file_name.clone(),
+    };
+    format!("{}:{}", file_data.source.as_ref().unwrap_or(&index.0), name)
+}
+
+/// the highest priority definition of each identifier, which is what gets extended or patched
+/// every file is a definition named after itself and every entry in its arrays one named by its name field
+/// patches aren't definitions themselves
+fn highest_priority_definitions(deserial_dump: &DeserializationDump) -> HashMap<String, (u8, DumpIndex)> {
+    let mut definitions: HashMap<String, (u8, DumpIndex)> = HashMap::new();
+    for (module_name, data_stack) in deserial_dump.iter() {
+        for (i, (file_name, file_data)) in data_stack.iter().enumerate() {
+            if file_data.patch.is_some() { continue; }
+
+            let priority = file_data.priority.unwrap_or(0);
+            let source = file_data.source.as_ref().unwrap_or(module_name);
+            let entries = file_data.array_entries().into_iter().map(|(entry, name)| (Some(entry), name));
+
+            for (entry, name) in std::iter::once((None, file_name.clone())).chain(entries) {
+                let index = (module_name.clone(), i, entry);
+                let identifier = format!("{}:{}", source, name);
+
+                match definitions.get(&identifier) {
+                    Some((existing_priority, _)) if *existing_priority > priority => {},
+                    _ => { definitions.insert(identifier, (priority, index)); },
+                }
+            }
+        }
+    }
+    definitions
+}
+
+/// merges everything with extends = "source:name" over its parent, parents first
+fn resolve_inheritance(deserial_dump: &mut DeserializationDump, diagnostics: &mut Vec<ModuleLoadError>) {
+    let definitions = highest_priority_definitions(deserial_dump);
+    let children: Vec<DumpIndex> = definitions.values()
+        .map(|(_, index)| index.clone())
+        .filter(|index| index.2.is_none() && deserial_dump[&index.0][index.1].1.extends.is_some())
+        .collect();
+
+    let mut resolved = HashMap::new();
+    for child in children {
+        resolve_parent(&child, deserial_dump, &definitions, &mut resolved, &mut Vec::new(), diagnostics);
+    }
+}
+
+fn resolve_parent(child: &DumpIndex, deserial_dump: &mut DeserializationDump, definitions: &HashMap<String, (u8, DumpIndex)>, resolved: &mut HashMap<DumpIndex, bool>, chain: &mut Vec<DumpIndex>, diagnostics: &mut Vec<ModuleLoadError>) {
+    if resolved.contains_key(child) { return; }
+
+    let file_data = &deserial_dump[&child.0][child.1].1;
+    let path = file_data.path.clone();
+    // entries in arrays can't extend anything themselves
+    let parent_identifier = match (&child.2, &file_data.extends) {
+        (None, Some(parent_identifier)) => parent_identifier.clone(),
+        _ => {
+            resolved.insert(child.clone(), true);
+            return;
+        },
+    };
+
+    if let Some(start) = chain.iter().position(|index| index == child) {
+        let mut cycle: Vec<String> = chain[start..].iter().map(|index| dump_identifier(deserial_dump, index)).collect();
+        cycle.push(dump_identifier(deserial_dump, child));
+        diagnostics.push(ModuleLoadError::new(path.as_deref(), ModuleLoadErrorKind::InheritanceCycle(cycle)));
+
+        // so the cycle is only reported once
+        for index in &chain[start..] {
+            resolved.insert(index.clone(), false);
+        }
+        return;
+    }
+
+    let parent = match definitions.get(&parent_identifier) {
+        Some((_, parent)) => parent.clone(),
+        None => {
+            diagnostics.push(ModuleLoadError::new(
+                path.as_deref(),
+                ModuleLoadErrorKind::MissingParent { name: dump_identifier(deserial_dump, child), parent: parent_identifier }
+            ));
+            resolved.insert(child.clone(), true);
+            return;
+        },
+    };
+
+    chain.push(child.clone());
+    resolve_parent(&parent, deserial_dump, definitions, resolved, chain, diagnostics);
+    chain.pop();
+
+    // anything in or extending a cycle isn't merged
+    if resolved.get(&parent) != Some(&true) {
+        resolved.insert(child.clone(), false);
+        return;
+    }
+
+    let parent_data = deserial_dump[&parent.0][parent.1].1.definition(parent.2);
+    deserial_dump.get_mut(&child.0).unwrap()[child.1].1.inherit_from(&parent_data);
+    resolved.insert(child.clone(), true);
+}
+
 // deserialization
-pub fn deserialize_modules_from_path(game_data: &mut DeserializationDump, path: &'static str) {
-    let dir = fs::read_dir(path).unwrap();
+/// deserializes and maps every module in path
+/// anything which fails to load is skipped and reported in the returned errors
+pub fn load_modules(path: &'static str, id_allocators: &mut IdAllocators) -> (GameDataDump, Vec<ModuleLoadError>) {
+    let mut diagnostics = Vec::new();
+
+    let mut deserial_dump: DeserializationDump = BTreeMap::new();
+    deserialize_modules_from_path(&mut deserial_dump, path, &mut diagnostics);
+    apply_patches(&mut deserial_dump, &mut diagnostics);
+    resolve_inheritance(&mut deserial_dump, &mut diagnostics);
+
+    let mut pre_map_dump: PreMapDump = Vec::new();
+    let mapped_dump = map_deserialized_dump(&mut pre_map_dump, &deserial_dump, id_allocators, &mut diagnostics);
+    debug_assert!(mapped_dump.mismatched_ids().is_empty(), "{:?}", mapped_dump.mismatched_ids());
+
+    for diagnostic in &diagnostics {
+        log_warn!("{}", diagnostic);
+    }
+
+    (mapped_dump, diagnostics)
+}
+
+/// runs the whole loading pipeline on path, returning every problem found
+pub fn validate_modules(path: &'static str) -> Vec<ModuleLoadError> {
+    let mut id_allocators = IdAllocators::new();
+    load_modules(path, &mut id_allocators).1
+}
+
+pub fn deserialize_modules_from_path(game_data: &mut DeserializationDump, path: &'static str, diagnostics: &mut Vec<ModuleLoadError>) {
+    let dir = match sorted_dir(path) {
+        Ok(dir) => dir,
+        Err(e) => {
+            diagnostics.push(ModuleLoadError::new(Some(Path::new(path)), ModuleLoadErrorKind::Io(e.to_string())));
+            return;
+        },
+    };
     
     for data in dir {
-        let data = data.unwrap();
+        let data = match data {
+            Ok(data) => data,
+            Err(e) => {
+                diagnostics.push(ModuleLoadError::new(Some(Path::new(path)), ModuleLoadErrorKind::Io(e.to_string())));
+                continue;
+            },
+        };
 
         let mut data_stack = Vec::new();
-        load_module_data(&mut data_stack, Ok(&data));
+        load_module_data(&mut data_stack, &data.path(), diagnostics);
 
         let data_key = os_string_to_string(data.file_name());
         game_data.insert(data_key, data_stack);
     }
 
-    post_deserialization_events(game_data);
+    post_deserialization_events(game_data, diagnostics);
 }
 
 /// run post deserialization lua events
-fn post_deserialization_events(game_data: &mut DeserializationDump) {
+fn post_deserialization_events(game_data: &mut DeserializationDump, diagnostics: &mut Vec<ModuleLoadError>) {
 
     lua().lock().unwrap().context(|lua_context| {
         let globals = lua_context.globals();
@@ -480,7 +1318,7 @@ fn post_deserialization_events(game_data: &mut DeserializationDump) {
                     for pair in post_deserialization_events.pairs::<Value, Function>() {
                         let pair = pair.unwrap();
                         if let Err(e) = pair.1.call::<_, Value>(()) {
-                            write_to_debug_pretty(format!("{:?}:\n{:?}", pair.0, e));
+                            diagnostics.push(ModuleLoadError::new(None, ModuleLoadErrorKind::Script(e.to_string())));
                         }
                     }
                 }
@@ -490,16 +1328,12 @@ fn post_deserialization_events(game_data: &mut DeserializationDump) {
                 if let Ok(lua_game_data) = lua_init_info.get::<_, Table>("GameData") {
                     for pair in lua_game_data.pairs::<String, Table>() {
                         if let Ok((source, data_table)) = pair {
-                            match game_data.get_mut(&source) {
-                                Some(existing_data_stack) => {
-                                    data_table_to_data_stack(data_table, existing_data_stack);
-                                },
-                                None => {
-                                    let data_stack = Vec::new();
-                                    data_table_to_data_stack(data_table, &mut Vec::new());
-                                    game_data.insert(source, data_stack);
-                                },
+                            if !game_data.contains_key(&source) {
+                                diagnostics.push(ModuleLoadError::new(None, ModuleLoadErrorKind::UnknownSource(source.clone())));
                             }
+
+                            let data_stack = game_data.entry(source.clone()).or_default();
+                            data_table_to_data_stack(&source, data_table, data_stack, diagnostics);
                         }
                     }
                 }
@@ -508,112 +1342,167 @@ fn post_deserialization_events(game_data: &mut DeserializationDump) {
     });
 }
 
-fn data_table_to_data_stack(data_table: Table<'_>, data_stack: &mut Vec<(String, ModuleDeserialization)>) {
-    for pair in data_table.pairs::<String, Table>() {
-        if let Ok((name, data)) = pair {
-            let mod_deser = ModuleDeserialization {
-                _force_deser: None,
-                source: match data.get("source") {
-                    Ok(source) => Some(source),
-                    Err(_) => None,
-                },
-                priority: match data.get("priority") {
-                    Ok(priority) => Some(priority),
-                    Err(_) => None,
-                },
-                tile: match data.get::<_, Table<'_>>("tile") {
-                    Ok(tile) => Some(TileDeserialData {
-                        _force_deser: None,
-                        visual_data: None,
-                        solid: match tile.get("solid") {
-                            Ok(solid) => Some(solid),
-                            Err(_) => Some(true),
-                        },
-                        world_gen_weight: match tile.get("world_gen_weight") {
-                            Ok(weight) => Some(weight),
-                            Err(_) => None,
-                        },
-                    }),
-                    Err(_) => None,
-                },
-                ground: None,
-                item: match data.get::<_, Table<'_>>("item") {
-                    Ok(item) => Some(ItemDeserialData {
-                        _force_deser: None,
-                        visual_data: None,
-                    }),
-                    Err(_) => None,
-                },
-                visible_thing: None,
-                thing: None,
-                byte_stream: None,
-                visual_data: match data.get::<_, Table<'_>>("visual_data") {
-                    Ok(visual_data) => Some(VisualDeserData {
-                        _force_deser: None,
-                        identifier: match visual_data.get("identifier") {
-                            Ok(ident) => Some(ident),
-                            Err(_) => None,
-                        },
-                        name: match visual_data.get("name") {
-                            Ok(name) => Some(name),
-                            Err(_) => None,
-                        },
-                        character_left: match visual_data.get("character_left") {
-                            Ok(cl) => Some(cl),
-                            Err(_) => None,
-                        },
-                        character_right: match visual_data.get("character_right") {
-                            Ok(cr) => Some(cr),
-                            Err(_) => None,
-                        },
-                        text_color_left: match visual_data.get::<_, [u8;3]>("text_color_left") {
-                            Ok(tl) => Some((tl[0], tl[1], tl[2])),
-                            Err(_) => None,
-                        },
-                        text_color_right: match visual_data.get::<_, [u8;3]>("text_color_right") {
-                            Ok(tr) => Some((tr[0], tr[1], tr[2])),
-                            Err(_) => None,
-                        },
-                        back_color_left: match visual_data.get::<_, [u8;3]>("back_color_left") {
-                            Ok(bl) => Some((bl[0], bl[1], bl[2])),
-                            Err(_) => None,
-                        },
-                        back_color_right: match visual_data.get::<_, [u8;3]>("back_color_right") {
-                            Ok(br) => Some((br[0], br[1], br[2])),
-                            Err(_) => None,
-                        },
-                    }),
-                    Err(_) => None,
-                },
-            };
+/// deserializes every entry in a Core.InitializationInfo.GameData[source] table
+/// the same way as a module file with the entry's name
+fn data_table_to_data_stack(source: &str, data_table: Table<'_>, data_stack: &mut Vec<(String, ModuleDeserialization)>, diagnostics: &mut Vec<ModuleLoadError>) {
+    for pair in data_table.pairs::<String, Value>() {
+        let (name, data) = match pair {
+            Ok(pair) => pair,
+            Err(e) => {
+                diagnostics.push(ModuleLoadError::new(None, ModuleLoadErrorKind::Script(e.to_string())));
+                continue;
+            },
+        };
+        let location = format!("GameData.{}.{}", source, name);
+
+        let mut unknown_keys = Vec::new();
+        let deserialized: Result<ModuleDeserialization, _> = serde_ignored::deserialize(LuaDeserializer { value: data }, |key_path| {
+            let key_path = key_path.to_string();
+            unknown_keys.push(key_path.split('.').filter(|part| *part != "?").collect::<Vec<_>>().join("."));
+        });
 
-            data_stack.push((name, mod_deser));
+        match deserialized {
+            Ok(mod_deser) => data_stack.push((name, mod_deser)),
+            Err(e) => diagnostics.push(ModuleLoadError::new(None, ModuleLoadErrorKind::Parse(format!("{}: {}", location, e)))),
         }
 
+        for key in unknown_keys {
+            diagnostics.push(ModuleLoadError::new(None, ModuleLoadErrorKind::UnknownKey(format!("{}.{}", location, key))));
+        }
     }
 }
 
+fn load_module_data(data_stack: &mut Vec<(String, ModuleDeserialization)>, path: &Path, diagnostics: &mut Vec<ModuleLoadError>) {
+    if path.is_dir() {
+        let dir = match sorted_dir(path) {
+            Ok(dir) => dir,
+            Err(e) => {
+                diagnostics.push(ModuleLoadError::new(Some(path), ModuleLoadErrorKind::Io(e.to_string())));
+                return;
+            },
+        };
+        for data in dir {
+            match data {
+                Ok(data) => load_module_data(data_stack, &data.path(), diagnostics),
+                Err(e) => diagnostics.push(ModuleLoadError::new(Some(path), ModuleLoadErrorKind::Io(e.to_string()))),
+            }
+        }
+
+    } else if let Some(format) = DataFormat::from_path(path) {
+        let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(name) => name.to_owned(),
+            None => return,
+        };
+
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                diagnostics.push(ModuleLoadError::new(Some(path), ModuleLoadErrorKind::Io(e.to_string())));
+                return;
+            },
+        };
 
-fn load_module_data(data_stack: &mut Vec<(String, ModuleDeserialization)>, data: Result<&DirEntry, &std::io::Error>) {
-    if dir_entry_is_dir(data) {
-        let data = fs::read_dir(data.unwrap().path()).unwrap();
-        for data in data {
-            load_module_data(data_stack, data.as_ref());
+        let mut unknown_keys = Vec::new();
+        match deserialize_module_file(format, path, &contents, &mut unknown_keys) {
+            Ok(mut mod_deser) => {
+                mod_deser.path = Some(path.to_path_buf());
+                data_stack.push((name, mod_deser));
+            },
+            Err(e) => diagnostics.push(*e),
         }
 
-    } else {
-        let data = data.unwrap();
-        if let Some(extension) = data.path().extension() {
-            if extension == "toml" {
-                let toml_data = (
-                    os_string_to_string(data.file_name()).trim_end_matches(".toml").to_owned(), 
-                    toml::from_str(&fs::read_to_string(data.path()).unwrap()).unwrap()
-                );
-                data_stack.push(toml_data);
-            }
+        for key in unknown_keys {
+            diagnostics.push(
+                ModuleLoadError::new(Some(path), ModuleLoadErrorKind::UnknownKey(key.clone()))
+                    .at_span(&contents, key_span(&contents, &key))
+            );
+        }
+    }
+}
+
+/// The formats module data files can be written in, picked by file extension
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DataFormat {
+    Toml,
+    /// with implicit_some, so optional fields don't need Some(...)
+    Ron,
+    Json,
+}
+
+impl DataFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "toml" => Some(Self::Toml),
+            "ron" => Some(Self::Ron),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+/// deserializes a module data file in any format, unknown keys are added to unknown_keys
+/// the error is boxed as it's much bigger than what's returned when it works
+fn deserialize_module_file(format: DataFormat, path: &Path, contents: &str, unknown_keys: &mut Vec<String>) -> Result<ModuleDeserialization, Box<ModuleLoadError>> {
+    let on_unknown_key = |key_path: serde_ignored::Path| {
+        // options show up as '?' in the path
+        let key_path = key_path.to_string();
+        unknown_keys.push(key_path.split('.').filter(|part| *part != "?").collect::<Vec<_>>().join("."));
+    };
+    let parse_error = |message: String| {
+        let mut message = message;
+        if message.contains("expected a tuple of size 3") {
+            message.push_str(", colors are written as [r, g, b]");
+        }
+        ModuleLoadError::new(Some(path), ModuleLoadErrorKind::Parse(message))
+    };
+
+    match format {
+        DataFormat::Toml => {
+            serde_ignored::deserialize(toml::Deserializer::new(contents), on_unknown_key)
+                .map_err(|e| Box::new(parse_error(e.message().to_owned()).at_span(contents, e.span())))
+        },
+        DataFormat::Ron => {
+            let ron_error = |e: ron::error::SpannedError| {
+                Box::new(parse_error(e.code.to_string()).at_position(e.position.line, e.position.col))
+            };
+            let options = ron::Options::default().with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME);
+            let mut deserializer = ron::Deserializer::from_str_with_options(contents, options).map_err(ron_error)?;
+
+            let mod_deser = serde_ignored::deserialize(&mut deserializer, on_unknown_key)
+                .map_err(|e| ron_error(deserializer.span_error(e)))?;
+            deserializer.end().map_err(|e| ron_error(deserializer.span_error(e)))?;
+            Ok(mod_deser)
+        },
+        DataFormat::Json => {
+            let json_error = |e: serde_json::Error| {
+                // the position is already in the error's fields
+                let message = e.to_string();
+                let message = message.split(" at line ").next().unwrap_or_default().to_owned();
+                Box::new(parse_error(message).at_position(e.line(), e.column()))
+            };
+            let mut deserializer = serde_json::Deserializer::from_str(contents);
+
+            let mod_deser = serde_ignored::deserialize(&mut deserializer, on_unknown_key).map_err(json_error)?;
+            deserializer.end().map_err(json_error)?;
+            Ok(mod_deser)
+        },
+    }
+}
+
+/// where the last part of a dotted key path is first written as a key in source
+fn key_span(source: &str, key_path: &str) -> Option<Range<usize>> {
+    let key = key_path.rsplit('.').next()?;
+    for (start, _) in source.match_indices(key) {
+        let before = source[..start].chars().last();
+        let after = source[start + key.len()..].trim_start_matches(['"', ' ']);
+
+        let starts_word = before.is_none_or(|c| !(c.is_alphanumeric() || c == '_'));
+        let is_key = after.starts_with(['=', ':', '.', ']']);
+        if starts_word && is_key {
+            return Some(start..start + key.len());
         }
-        
     }
+    None
 }
 
 /* TODO - make this ~*/
@@ -624,43 +1513,250 @@ pub fn load_module_data_from_persistent_mapping(path: &'static str) -> io::Resul
     Ok(())
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Clone, Debug, Default)]
 pub struct ModuleDeserialization {
     /// omit to not force deserialization
     _force_deser: Option<bool>,
 
+    /// where this was loaded from, set after deserializing
+    #[serde(skip)]
+    path: Option<PathBuf>,
+
     // by default the module name
     source: Option<String>,
     priority: Option<u8>,
 
+    /// identifier of the entry this inherits unset fields from, e.g. "conveyor_line_inc:tree"
+    extends: Option<String>,
+    /// templates are only used to be extended and aren't loaded themselves
+    template: Option<bool>,
+    /// identifier of an entry to merge this file's fields into, instead of defining a new one
+    patch: Option<String>,
+
     tile: Option<TileDeserialData>,
     ground: Option<GroundDeserialData>,
     item: Option<ItemDeserialData>,
+    entity: Option<EntityDeserialData>,
 
     visible_thing: Option<VisibleThingDeserialData>,
     thing: Option<ThingDeserialData>,
     byte_stream: Option<ByteStreamDeserialData>,
 
     visual_data: Option<VisualDeserData>,
+
+    // arrays of named entries, each with its own visual_data
+    tiles: Option<Vec<TileDeserialData>>,
+    grounds: Option<Vec<GroundDeserialData>>,
+    items: Option<Vec<ItemDeserialData>>,
+    entities: Option<Vec<EntityDeserialData>>,
+    visible_things: Option<Vec<VisibleThingDeserialData>>,
+    things: Option<Vec<ThingDeserialData>>,
+    byte_streams: Option<Vec<ByteStreamDeserialData>>,
 }
 
 // end point data
 #[derive(Debug)]
 pub struct VisibleThingType {
-    type_identifier: String,
-    identifier: u16,
-    text_display: TextDisplay,
-    color_display: ColorDisplay
+    pub type_identifier: String,
+    pub identifier: u16,
+    pub bytes: Vec<u8>,
+    pub text_display: TextDisplay,
+    pub color_display: ColorDisplay
 }
 
 #[derive(Debug)]
 pub struct ThingType {
-    type_identifier: String,
-    identifier: u16,
+    pub type_identifier: String,
+    pub identifier: u16,
+    pub bytes: Vec<u8>,
 }
 
 #[derive(Debug)]
 pub struct ByteStream {
-    identifier: u16,
-    bytes: Vec<u8>
-}
\ No newline at end of file
+    pub identifier: u16,
+    pub type_identifier: Option<String>,
+    pub bytes: Vec<u8>
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::{Arc, Mutex}, time::{Duration, Instant}};
+
+    use rlua::Lua;
+
+    use super::*;
+
+    /// one module with entries of every data type, some in arrays and some in json and ron
+    const ID_FIXTURE_PATH: &str = r"tests/fixtures/modules";
+
+    /// checks every identifier of a data type maps to an entry with that id and nothing else is mapped
+    fn check_ids<T>(types: &MappedGameData<T>, identifiers: &BiMap<String, u16>, id_of: impl Fn(&T) -> u16, expected: &[&str]) {
+        let mut mapped: Vec<&str> = identifiers.left_values().map(String::as_str).collect();
+        mapped.sort();
+        let mut expected = expected.to_vec();
+        expected.sort();
+        assert_eq!(mapped, expected);
+        assert_eq!(types.len(), identifiers.len());
+
+        for (identifier, id) in identifiers {
+            let mapped_type = types.get(id).unwrap_or_else(|| panic!("{} has id {}, which has no entry", identifier, id));
+            assert_eq!(id_of(mapped_type), *id, "{} is mapped under the wrong id", identifier);
+        }
+    }
+
+    #[test]
+    fn ids_match_identifiers_in_every_category() {
+        let _globals = crate::GLOBALS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        // loading runs the post deserialization events, there aren't any without scripts
+        unsafe { crate::LUA = Some(Arc::new(Mutex::new(Lua::new()))) }
+
+        let (mapped_dump, diagnostics) = load_modules(ID_FIXTURE_PATH, &mut IdAllocators::new());
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert!(mapped_dump.mismatched_ids().is_empty(), "{:?}", mapped_dump.mismatched_ids());
+
+        let (data, identifiers) = (&mapped_dump.game_data, &mapped_dump.identifiers);
+        check_ids(&data.tile_types, &identifiers.tile_types, |tile_type| tile_type.identifier, &["id_fixture:stone", "id_fixture:sand", "id_fixture:gravel"]);
+        check_ids(&data.ground_types, &identifiers.ground_types, |ground_type| ground_type.identifier, &["id_fixture:dirt", "id_fixture:mud"]);
+        check_ids(&data.item_types, &identifiers.item_types, |item_type| item_type.identifier, &["id_fixture:pebble", "id_fixture:plank"]);
+        check_ids(&data.entity_types, &identifiers.entity_types, |entity_type| entity_type.identifier, &["id_fixture:beetle"]);
+        check_ids(&data.visible_thing_types, &identifiers.visible_thing_types, |thing_type| thing_type.identifier, &["id_fixture:marker"]);
+        check_ids(&data.thing_types, &identifiers.thing_types, |thing_type| thing_type.identifier, &["id_fixture:recipe"]);
+        check_ids(&data.byte_streams, &identifiers.byte_streams, |byte_stream| byte_stream.identifier, &["id_fixture:noise", "id_fixture:silence"]);
+    }
+
+    /// maps count synthetic definitions spread over every data type and a few modules,
+    /// every tenth one defined twice at a higher priority
+    /// checks the ids of what was mapped and returns how long mapping took
+    fn time_mapping(count: usize) -> Duration {
+        let visual_data = serde_json::json!({ "character_left": "#", "character_right": "#" });
+        let mut deserial_dump: DeserializationDump = BTreeMap::new();
+        let mut expected_counts = [0; 7];
+
+        for i in 0..count {
+            let (key, data) = match i % 7 {
+                0 => ("tile", serde_json::json!({ "tile": { "solid": i % 2 == 0 }, "visual_data": visual_data })),
+                1 => ("ground", serde_json::json!({ "ground": {}, "visual_data": visual_data })),
+                2 => ("item", serde_json::json!({ "item": {}, "visual_data": visual_data })),
+                3 => ("visible_thing", serde_json::json!({ "visible_thing": { "type_identifier": "bench" }, "visual_data": visual_data })),
+                4 => ("thing", serde_json::json!({ "thing": { "type_identifier": "bench" } })),
+                5 => ("entity", serde_json::json!({ "entity": {}, "visual_data": visual_data })),
+                _ => ("byte_stream", serde_json::json!({ "byte_stream": { "bytes": [i % 256] } })),
+            };
+            expected_counts[i % 7] += 1;
+
+            let module = deserial_dump.entry(format!("bench_{}", i % 8)).or_default();
+            if i % 10 == 0 {
+                let mut override_data = data.clone();
+                override_data["priority"] = serde_json::json!(1);
+                module.push((format!("{}_{}", key, i), serde_json::from_value(override_data).unwrap()));
+            }
+            module.push((format!("{}_{}", key, i), serde_json::from_value(data).unwrap()));
+        }
+
+        let mut id_allocators = IdAllocators::new();
+        let mut diagnostics = Vec::new();
+        let mut pre_map_dump: PreMapDump = Vec::new();
+
+        let start = Instant::now();
+        let mapped_dump = map_deserialized_dump(&mut pre_map_dump, &deserial_dump, &mut id_allocators, &mut diagnostics);
+        let elapsed = start.elapsed();
+
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        let data = &mapped_dump.game_data;
+        let mapped_counts = [data.tile_types.len(), data.ground_types.len(), data.item_types.len(), data.visible_thing_types.len(), data.thing_types.len(), data.entity_types.len(), data.byte_streams.len()];
+        assert_eq!(mapped_counts, expected_counts);
+        let mismatched = mapped_dump.mismatched_ids();
+        assert!(mismatched.is_empty(), "{}", mismatched.join("\n"));
+
+        elapsed
+    }
+
+    #[test]
+    #[ignore = "timing based, too unreliable on a busy machine"]
+    fn mapping_time_is_linear() {
+        // the fastest of a few runs, so one slow run doesn't fail the test
+        let fastest = |count| (0..3).map(|_| time_mapping(count)).min().unwrap();
+        let single = fastest(25000);
+        let double = fastest(50000);
+
+        // twice the definitions should take about twice as long, quadratic mapping would be about 4 times
+        let ratio = double.as_secs_f64() / single.as_secs_f64();
+        assert!(ratio < 3.0, "mapping 50000 definitions took {:.2} times as long as 25000 ({:?} and {:?})", ratio, double, single);
+    }
+
+    #[test]
+    fn id_allocator_runs_out_instead_of_overflowing() {
+        let mut id_allocator = IdAllocator::new();
+        for expected in 0..=u16::MAX {
+            assert_eq!(id_allocator.allocate(), Some(expected));
+        }
+        assert_eq!(id_allocator.allocate(), None);
+    }
+
+    /// a dump of (module name, file name, file contents)
+    fn dump_from_json(files: Vec<(&str, &str, serde_json::Value)>) -> DeserializationDump {
+        let mut deserial_dump: DeserializationDump = BTreeMap::new();
+        for (module_name, file_name, data) in files {
+            deserial_dump.entry(module_name.to_owned()).or_default().push((file_name.to_owned(), serde_json::from_value(data).unwrap()));
+        }
+        deserial_dump
+    }
+
+    fn boulders() -> (&'static str, &'static str, serde_json::Value) {
+        ("arrays", "rocks", serde_json::json!({
+            "tiles": [
+                { "name": "pebble", "visual_data": { "character_left": ".", "character_right": "." } },
+                { "name": "boulder", "solid": false, "world_gen_weight": 2.0, "visual_data": { "character_left": "O", "character_right": "O" } },
+            ]
+        }))
+    }
+
+    #[test]
+    fn entries_in_arrays_can_be_extended() {
+        let mut deserial_dump = dump_from_json(vec![
+            boulders(),
+            ("child", "big_boulder", serde_json::json!({ "extends": "arrays:boulder", "tile": { "world_gen_weight": 5.0 } })),
+        ]);
+
+        let mut diagnostics = Vec::new();
+        resolve_inheritance(&mut deserial_dump, &mut diagnostics);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        let child = &deserial_dump["child"][0].1;
+        let tile = child.tile.as_ref().unwrap();
+        assert_eq!(tile.solid, Some(false));
+        assert_eq!(tile.world_gen_weight, Some(5.0));
+        assert_eq!(tile.name, None);
+        assert_eq!(child.visual_data.as_ref().unwrap().character_left.as_deref(), Some("O"));
+
+        let mut pre_map_dump: PreMapDump = Vec::new();
+        let mapped_dump = map_deserialized_dump(&mut pre_map_dump, &deserial_dump, &mut IdAllocators::new(), &mut diagnostics);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert!(mapped_dump.identifiers.tile_types.contains_left("child:big_boulder"));
+    }
+
+    #[test]
+    fn entries_in_arrays_can_be_patched() {
+        let mut deserial_dump = dump_from_json(vec![
+            boulders(),
+            ("patcher", "boulder", serde_json::json!({ "patch": "arrays:boulder", "tile": { "solid": true }, "visual_data": { "character_left": "0" } })),
+        ]);
+
+        let mut diagnostics = Vec::new();
+        apply_patches(&mut deserial_dump, &mut diagnostics);
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        let tiles = deserial_dump["arrays"][0].1.tiles.as_ref().unwrap();
+        let boulder = &tiles[1];
+        assert_eq!(boulder.name.as_deref(), Some("boulder"));
+        assert_eq!(boulder.solid, Some(true));
+        assert_eq!(boulder.world_gen_weight, Some(2.0));
+        let visual_data = boulder.visual_data.as_ref().unwrap();
+        assert_eq!(visual_data.character_left.as_deref(), Some("0"));
+        assert_eq!(visual_data.character_right.as_deref(), Some("O"));
+
+        // the rest of the array is left alone
+        assert_eq!(tiles[0].solid, None);
+        assert_eq!(tiles[0].visual_data.as_ref().unwrap().character_left.as_deref(), Some("."));
+    }
+}
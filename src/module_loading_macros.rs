@@ -1,22 +1,28 @@
+/// fields are written as `/// description` `[required] name: type = "default"`,
+/// where the doc comment, [required] and default are all optional
 macro_rules! deserializable_module_data_no_visual {
     (
         $deser_vis:vis struct $deser_name:ident
-        $($field_vis:vis $field:ident: $t:ty),*
+        $( $(#[doc = $doc:literal])* $([$required:ident])? $field:ident: $t:ty $(= $default:literal)? ),*
     ) => {
         #[derive(Deserialize, Clone, Debug)]
         $deser_vis struct $deser_name {
             /// omit to not force deserialization
             $deser_vis _force_deser: Option<bool>,
-            $($field_vis $field: Option<$t>),*
+            $( $(#[doc = $doc])* $field: Option<$t> ),*
         }
-    };
-    (
-        $deser_vis:vis struct $deser_name:ident
-    ) => {
-        #[derive(Deserialize, Clone, Debug)]
-        $deser_vis struct $deser_name {
-            /// omit to not force deserialization
-            $deser_vis _force_deser: Option<bool>,
+
+        impl_schema!(
+            $deser_name
+            $( $(#[doc = $doc])* $([$required])? $field: $t $(= $default)? ),*
+        );
+
+        impl $deser_name {
+            pub fn embedded_visual_data(&self) -> Option<&VisualDeserData> {
+                None
+            }
+
+            fn inherit_visual_data(&mut self, _parent: &Self) {}
         }
     };
 }
@@ -24,50 +30,43 @@ macro_rules! deserializable_module_data_no_visual {
 macro_rules! deserializable_module_data_yes_visual {
     (
         $deser_vis:vis struct $deser_name:ident
-        $($field_vis:vis $field:ident: $t:ty),*
+        $( $(#[doc = $doc:literal])* $([$required:ident])? $field:ident: $t:ty $(= $default:literal)? ),*
     ) => {
         #[derive(Deserialize, Clone, Debug)]
         $deser_vis struct $deser_name {
             /// omit to not force deserialization
             $deser_vis _force_deser: Option<bool>,
             $deser_vis visual_data: Option<VisualDeserData>,
-            $($field_vis $field: Option<$t>),*
+            $( $(#[doc = $doc])* $field: Option<$t> ),*
         }
-    };
-    (
-        $deser_vis:vis struct $deser_name:ident
-    ) => {
-        #[derive(Deserialize, Clone, Debug)]
-        $deser_vis struct $deser_name {
-            /// omit to not force deserialization
-            $deser_vis _force_deser: Option<bool>,
-            $deser_vis visual_data: Option<VisualDeserData>
+
+        impl_schema!(
+            $deser_name
+            $( $(#[doc = $doc])* $([$required])? $field: $t $(= $default)? ),*
+        );
+
+        impl $deser_name {
+            pub fn embedded_visual_data(&self) -> Option<&VisualDeserData> {
+                self.visual_data.as_ref()
+            }
+
+            fn inherit_visual_data(&mut self, parent: &Self) {
+                inherit_table(&mut self.visual_data, &parent.visual_data);
+            }
         }
     };
 }
 
 macro_rules! deserializable_module_data {
-    (   
-        [has_visual]
-
-        $deser_vis:vis struct $deser_name:ident
-        $($field_vis:vis $field:ident: $t:ty),*
-    ) => {
-        deserializable_module_data_yes_visual!{
-            $deser_vis struct $deser_name
-            $($field_vis $field: $t),*
-        }
-        impl_deserialization!(
-            $deser_vis $deser_name
-        );
-    };
     (
         [has_visual]
 
         $deser_vis:vis struct $deser_name:ident
+        $($fields:tt)*
     ) => {
         deserializable_module_data_yes_visual!{
             $deser_vis struct $deser_name
+            $($fields)*
         }
         impl_deserialization!(
             $deser_vis $deser_name
@@ -75,30 +74,70 @@ macro_rules! deserializable_module_data {
     };
     (
         $deser_vis:vis struct $deser_name:ident
-        $($field_vis:vis $field:ident: $t:ty),*
+        $($fields:tt)*
     ) => {
         deserializable_module_data_no_visual! {
             $deser_vis struct $deser_name
-            $($field_vis $field: $t),*
+            $($fields)*
         }
         impl_deserialization!(
             $deser_vis $deser_name
         );
     };
+}
+
+macro_rules! impl_schema {
     (
-        $deser_vis:vis struct $deser_name:ident
+        $name:ident
+        $( $(#[doc = $doc:literal])* $([$required:ident])? $field:ident: $t:ty $(= $default:literal)? ),*
     ) => {
-        deserializable_module_data_no_visual! {
-            $deser_vis struct $deser_name
-            $non_deser_vis struct $non_deser_name
+        impl $name {
+            pub const SCHEMA: &'static [FieldSchema] = &[
+                $(
+                    FieldSchema {
+                        name: stringify!($field),
+                        type_name: <$t as SchemaType>::TYPE_NAME,
+                        required: schema_required!($($required)?),
+                        default: schema_default!($($default)?),
+                        description: concat!($($doc),*),
+                    }
+                ),*
+            ];
+
+            /// names of the fields which were given a value
+            pub fn present_fields(&self) -> Vec<&'static str> {
+                #[allow(unused_mut)]
+                let mut present = Vec::new();
+                $(
+                    if self.$field.is_some() {
+                        present.push(stringify!($field));
+                    }
+                )*
+                present
+            }
         }
-        impl_deserialization!(
-            $deser_vis $deser_name
-        );
-        impl_priority!(
-            $non_deser_vis $non_deser_name
-        );
-    }
+
+        impl Inherit for $name {
+            fn inherit_from(&mut self, parent: &Self) {
+                $(
+                    if self.$field.is_none() {
+                        self.$field = parent.$field.clone();
+                    }
+                )*
+                self.inherit_visual_data(parent);
+            }
+        }
+    };
+}
+
+macro_rules! schema_required {
+    () => { false };
+    (required) => { true };
+}
+
+macro_rules! schema_default {
+    () => { None };
+    ($default:literal) => { Some($default) };
 }
 
 macro_rules! impl_deserialization {
@@ -106,10 +145,18 @@ macro_rules! impl_deserialization {
         $vis:vis $name:ident
     ) => {
         impl AsAny for $name {
-            $vis fn as_any(&self) -> &dyn Any {
+            fn as_any(&self) -> &dyn Any {
                 self
             }
         }
-        impl Deserialization for $name {}
+        impl Deserialization for $name {
+            fn embedded_visual_data(&self) -> Option<&VisualDeserData> {
+                $name::embedded_visual_data(self)
+            }
+
+            fn present_fields(&self) -> Vec<&'static str> {
+                $name::present_fields(self)
+            }
+        }
     };
-}
\ No newline at end of file
+}
@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+
+use crate::{ByteStreamDeserialData, EntityDeserialData, GroundDeserialData, ItemDeserialData, ThingDeserialData, TileDeserialData, VisibleThingDeserialData, VisualDeserData};
+
+pub const MODULE_REFERENCE_PATH: &str = r"docs/module_reference.md";
+
+/// Describes one field of a module data type, generated by deserializable_module_data!
+#[derive(Clone, Copy, Debug)]
+pub struct FieldSchema {
+    pub name: &'static str,
+    pub type_name: &'static str,
+    /// entries without a required field are skipped
+    pub required: bool,
+    /// what is used when the field is omitted, if anything
+    pub default: Option<&'static str>,
+    pub description: &'static str,
+}
+
+/// How a rust type is written in module files
+pub trait SchemaType {
+    const TYPE_NAME: &'static str;
+}
+
+impl SchemaType for bool { const TYPE_NAME: &'static str = "boolean"; }
+impl SchemaType for u64 { const TYPE_NAME: &'static str = "integer"; }
+impl SchemaType for f64 { const TYPE_NAME: &'static str = "float"; }
+impl SchemaType for String { const TYPE_NAME: &'static str = "string"; }
+impl SchemaType for (u8, u8, u8) { const TYPE_NAME: &'static str = "[r, g, b], each 0-255"; }
+impl SchemaType for Vec<u8> { const TYPE_NAME: &'static str = "array of integers 0-255"; }
+impl SchemaType for VisualDeserData { const TYPE_NAME: &'static str = "[visual_data] table"; }
+impl SchemaType for Vec<VisualDeserData> { const TYPE_NAME: &'static str = "array of [visual_data] tables"; }
+impl SchemaType for BTreeMap<String, VisualDeserData> { const TYPE_NAME: &'static str = "table of names to [visual_data] tables"; }
+
+/// Describes a table which can be put in a module data file
+#[derive(Clone, Copy, Debug)]
+pub struct DataTypeSchema {
+    /// the table name in the file, e.g. [tile]
+    pub key: &'static str,
+    /// the type name used internally and in diagnostics
+    pub data_type: &'static str,
+    /// the key for an array of named entries, e.g. [[tiles]]
+    pub array_key: Option<&'static str>,
+    /// if the file also needs a [visual_data] table
+    pub needs_visual_data: bool,
+    pub description: &'static str,
+    pub fields: &'static [FieldSchema],
+}
+
+/// fields at the top level of every module data file
+pub const FILE_SCHEMA: &[FieldSchema] = &[
+    FieldSchema {
+        name: "source",
+        type_name: "string",
+        required: false,
+        default: None,
+        description: "which module the data belongs to, defaults to the module's folder name. the identifier becomes source:file_name",
+    },
+    FieldSchema {
+        name: "priority",
+        type_name: "integer 0-255",
+        required: false,
+        default: Some("0"),
+        description: "when several files define the same identifier the highest priority is used",
+    },
+    FieldSchema {
+        name: "extends",
+        type_name: "string",
+        required: false,
+        default: None,
+        description: "identifier of another entry, e.g. `conveyor_line_inc:tree`, whose fields are used for any this doesn't set",
+    },
+    FieldSchema {
+        name: "template",
+        type_name: "boolean",
+        required: false,
+        default: Some("false"),
+        description: "templates are only used to be extended and aren't loaded themselves",
+    },
+    FieldSchema {
+        name: "patch",
+        type_name: "string",
+        required: false,
+        default: None,
+        description: "identifier of an entry to change instead of defining a new one. only the fields set here are replaced, patches apply in order of priority, then module name, then file name",
+    },
+];
+
+pub const MODULE_SCHEMA: &[DataTypeSchema] = &[
+    DataTypeSchema {
+        key: "tile",
+        array_key: Some("tiles"),
+        data_type: "tile",
+        needs_visual_data: true,
+        description: "something which sits on the map, one per cell",
+        fields: TileDeserialData::SCHEMA,
+    },
+    DataTypeSchema {
+        key: "ground",
+        array_key: Some("grounds"),
+        data_type: "ground",
+        needs_visual_data: true,
+        description: "what is shown under a tile",
+        fields: GroundDeserialData::SCHEMA,
+    },
+    DataTypeSchema {
+        key: "item",
+        array_key: Some("items"),
+        data_type: "item",
+        needs_visual_data: true,
+        description: "something which can be held in an inventory",
+        fields: ItemDeserialData::SCHEMA,
+    },
+    DataTypeSchema {
+        key: "entity",
+        array_key: Some("entities"),
+        data_type: "entity",
+        needs_visual_data: true,
+        description: "something which moves freely over the map, spawned by scripts with Core.Entities.spawn",
+        fields: EntityDeserialData::SCHEMA,
+    },
+    DataTypeSchema {
+        key: "visible_thing",
+        array_key: Some("visible_things"),
+        data_type: "vis_thing",
+        needs_visual_data: true,
+        description: "arbitrary data for scripts, which can be displayed",
+        fields: VisibleThingDeserialData::SCHEMA,
+    },
+    DataTypeSchema {
+        key: "thing",
+        array_key: Some("things"),
+        data_type: "thing",
+        needs_visual_data: false,
+        description: "arbitrary data for scripts",
+        fields: ThingDeserialData::SCHEMA,
+    },
+    DataTypeSchema {
+        key: "byte_stream",
+        array_key: Some("byte_streams"),
+        data_type: "byte_stream",
+        needs_visual_data: false,
+        description: "raw bytes for scripts",
+        fields: ByteStreamDeserialData::SCHEMA,
+    },
+    DataTypeSchema {
+        key: "visual_data",
+        array_key: None,
+        data_type: "vis_data",
+        needs_visual_data: false,
+        description: "how the other data in the same file is displayed",
+        fields: VisualDeserData::SCHEMA,
+    },
+];
+
+pub fn schema_for(data_type: &str) -> Option<&'static DataTypeSchema> {
+    MODULE_SCHEMA.iter().find(|schema| schema.data_type == data_type)
+}
+
+fn push_field_table(reference: &mut String, fields: &[FieldSchema]) {
+    reference.push_str("| field | type | required | default | description |\n");
+    reference.push_str("| --- | --- | --- | --- | --- |\n");
+    for field in fields {
+        reference.push_str(&format!(
+            "| `{}` | {} | {} | {} | {} |\n",
+            field.name,
+            field.type_name,
+            if field.required { "yes" } else { "no" },
+            field.default.map(|default| format!("`{}`", default)).unwrap_or_else(|| String::from("-")),
+            field.description.trim(),
+        ));
+    }
+}
+
+/// the reference written to MODULE_REFERENCE_PATH by --schema
+pub fn module_reference_markdown() -> String {
+    let mut reference = String::new();
+
+    reference.push_str("# Module data reference\n\n");
+    reference.push_str("Generated by `conveyor_line_inc --schema`, don't edit by hand.\n\n");
+    reference.push_str("Each `.toml`, `.ron` or `.json` file in a module defines data named after the file. ");
+    reference.push_str("Keys which aren't listed here are reported as unknown by `--check`.\n\n");
+    reference.push_str("All three formats hold the same tables. ");
+    reference.push_str("In RON optional fields are written without `Some(...)` and tables are structs, e.g. `(tile: (solid: false))`.\n\n");
+    reference.push_str("Scripts can define the same data in `Core.InitializationInfo.GameData[source][name]`, ");
+    reference.push_str("e.g. `{ tile = { solid = false }, visual_data = { character_left = \",\" } }`.\n\n");
+
+    reference.push_str("Instead of one entry named after the file, a file can hold arrays of entries, ");
+    reference.push_str("each with a `name` and its own visual data:\n\n");
+    reference.push_str("```toml\n[[tiles]]\nname = \"twig\"\nsolid = false\n\n[tiles.visual_data]\ncharacter_left = \",\"\n```\n\n");
+    reference.push_str("Entries from both forms share identifiers, so `twig.toml` and a `[[tiles]]` entry named `twig` in the same module collide.\n\n");
+
+    reference.push_str("## Top level\n\n");
+    push_field_table(&mut reference, FILE_SCHEMA);
+
+    for schema in MODULE_SCHEMA {
+        reference.push_str(&format!("\n## [{}]\n\n{}.", schema.key, capitalize(schema.description)));
+        if schema.needs_visual_data {
+            reference.push_str(" Needs a `[visual_data]` table in the same file.");
+        }
+        if let Some(array_key) = schema.array_key {
+            reference.push_str(&format!(" Several can be defined in one file with `[[{}]]`.", array_key));
+        }
+        reference.push_str("\n\n");
+
+        if schema.fields.is_empty() {
+            reference.push_str("No fields.\n");
+        } else {
+            push_field_table(&mut reference, schema.fields);
+        }
+    }
+
+    reference
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -1,4 +1,4 @@
-use std::{ffi::OsString, fs::DirEntry};
+use std::{ffi::OsString, fs::{self, DirEntry}, io, path::Path};
 
 pub const MODULES_PATH: &str = r#"resources/modules"#;
 
@@ -9,6 +9,14 @@ pub fn dir_entry_is_dir(dir_entry: Result<&DirEntry, &std::io::Error>) -> bool {
     }
 }
 
+/// like fs::read_dir, but sorted by path, as read_dir's order depends on the file system
+/// so everything which happens in load order, like id allocation and event order, is the same everywhere
+pub fn sorted_dir<P: AsRef<Path>>(path: P) -> io::Result<Vec<io::Result<DirEntry>>> {
+    let mut entries: Vec<_> = fs::read_dir(path)?.collect();
+    entries.sort_by_key(|entry| entry.as_ref().ok().map(DirEntry::path));
+    Ok(entries)
+}
+
 pub fn os_string_to_string(os_string: OsString) -> String {
     String::from(os_string.to_str().unwrap())
 }
\ No newline at end of file
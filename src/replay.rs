@@ -0,0 +1,263 @@
+use std::{fs::{self, File}, io::{BufWriter, Write}, panic};
+
+use crossterm::event::KeyEvent;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{action_from_input, call_key_events, entities, ground_map, player, step, tile_map, world};
+
+pub static mut RECORDER: Option<Recorder> = None;
+/// safe unsafe action lolz
+/// None unless --record was given
+// only used from the main thread, or the panic hook once the game has stopped
+#[allow(static_mut_refs)]
+pub fn recorder() -> Option<&'static mut Recorder> { unsafe { RECORDER.as_mut() } }
+
+/// records from now on, the end of the recording is written by finish_recording or when the game panics
+pub fn start_recording(recorder: Recorder) {
+    unsafe { RECORDER = Some(recorder) }
+
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        finish_recording();
+        default_hook(info);
+    }));
+}
+
+/// writes the end of the recording, if there is one, call before exiting
+/// only the first call writes anything
+// see recorder
+#[allow(static_mut_refs)]
+pub fn finish_recording() {
+    if let Some(mut recorder) = unsafe { RECORDER.take() } {
+        recorder.finish();
+    }
+}
+
+/// Something the player did which scripts see, commands go through action_from_input and keys through call_key_events
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedInput {
+    Command(String),
+    Key(KeyEvent),
+}
+
+/// One line of a recording, every recording starts with Start
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "line", rename_all = "snake_case")]
+enum RecordLine {
+    Start { seed: u64 },
+    Input {
+        tick: u64,
+        #[serde(flatten)]
+        input: RecordedInput,
+    },
+    /// the world hash when recording stopped, recordings without one can still be replayed
+    End { tick: u64, hash: String },
+}
+
+/// Writes inputs with the tick they happened on to a file, one json object per line
+/// lines are flushed straight away so a crash still leaves a usable recording
+pub struct Recorder {
+    file: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn create(path: &str, seed: u64) -> std::io::Result<Self> {
+        let mut recorder = Self { file: BufWriter::new(File::create(path)?) };
+        recorder.write(&RecordLine::Start { seed })?;
+        Ok(recorder)
+    }
+
+    /// inputs which fail to write are logged and dropped, the game goes on
+    pub fn record(&mut self, input: RecordedInput) {
+        if let Err(e) = self.write(&RecordLine::Input { tick: world().tick(), input }) {
+            log_error!("couldn't record input: {}", e);
+        }
+    }
+
+    /// writes the current world hash, see finish_recording
+    fn finish(&mut self) {
+        if let Err(e) = self.write(&RecordLine::End { tick: world().tick(), hash: format!("{:016x}", world_hash()) }) {
+            log_error!("couldn't finish recording: {}", e);
+        }
+    }
+
+    fn write(&mut self, line: &RecordLine) -> std::io::Result<()> {
+        serde_json::to_writer(&mut self.file, line)?;
+        self.file.write_all(b"\n")?;
+        self.file.flush()
+    }
+}
+
+/// A recording read back from a file
+pub struct Replay {
+    pub seed: u64,
+    /// in the order they happened, ticks never go down
+    pub inputs: Vec<(u64, RecordedInput)>,
+    /// the tick recording stopped on and the world hash it had then
+    pub end: Option<(u64, u64)>,
+}
+
+impl Replay {
+    /// the error is meant to be shown to the player
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("couldn't read {}: {}", path, e))?;
+
+        let mut seed = None;
+        let mut inputs: Vec<(u64, RecordedInput)> = Vec::new();
+        let mut end = None;
+
+        for (i, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let line_number = i + 1;
+            let line = serde_json::from_str(line).map_err(|e| format!("{}:{}: {}", path, line_number, e))?;
+
+            match (line, seed, end) {
+                (RecordLine::Start { seed: start_seed }, None, _) => seed = Some(start_seed),
+                (RecordLine::Start { .. }, Some(_), _) => return Err(format!("{}:{}: more than one start line", path, line_number)),
+                (_, None, _) => return Err(format!("{}:{}: the first line must be the start line", path, line_number)),
+                (_, _, Some(_)) => return Err(format!("{}:{}: nothing can come after the end line", path, line_number)),
+                (RecordLine::Input { tick, input }, _, None) => {
+                    if inputs.last().is_some_and(|(last_tick, _)| tick < *last_tick) {
+                        return Err(format!("{}:{}: tick {} is before the line above it", path, line_number, tick));
+                    }
+                    inputs.push((tick, input));
+                },
+                (RecordLine::End { tick, hash }, _, None) => {
+                    if inputs.last().is_some_and(|(last_tick, _)| tick < *last_tick) {
+                        return Err(format!("{}:{}: the end tick {} is before the last input", path, line_number, tick));
+                    }
+                    let hash = u64::from_str_radix(&hash, 16).map_err(|e| format!("{}:{}: bad hash {:?}: {}", path, line_number, hash, e))?;
+                    end = Some((tick, hash));
+                },
+            }
+        }
+
+        match seed {
+            Some(seed) => Ok(Self { seed, inputs, end }),
+            None => Err(format!("{} is empty", path)),
+        }
+    }
+}
+
+/// feeds a replay's inputs back on the ticks they were recorded on, for --replay
+/// the world must already be made from the replay's seed, prints the outcome and returns the exit code
+pub fn run_replay(replay: &Replay) -> i32 {
+    let end_tick = replay.end.map(|(tick, _)| tick)
+        .or_else(|| replay.inputs.last().map(|(tick, _)| *tick))
+        .unwrap_or(0);
+
+    let mut inputs = replay.inputs.iter().peekable();
+    loop {
+        while let Some((_, input)) = inputs.next_if(|(tick, _)| *tick <= world().tick()) {
+            match input {
+                RecordedInput::Command(command) => action_from_input(command),
+                RecordedInput::Key(event) => call_key_events(event),
+            }
+        }
+
+        if world().tick() >= end_tick { break; }
+        step();
+    }
+
+    let hash = world_hash();
+    match replay.end {
+        Some((_, expected)) if expected != hash => {
+            eprintln!("replay diverged at tick {}: expected hash {:016x}, got {:016x}", end_tick, expected, hash);
+            1
+        },
+        Some(_) => {
+            println!("replay matched at tick {}, hash {:016x}", end_tick, hash);
+            0
+        },
+        None => {
+            println!("replay finished at tick {} with no hash to check, hash {:016x}", end_tick, hash);
+            0
+        },
+    }
+}
+
+/// FNV-1a, std's hasher isn't guaranteed to give the same result between builds
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+}
+
+/// a hash of everything the simulation owns, the same seed and inputs always give the same hash
+/// lua state isn't included, only what it has done to the world
+pub fn world_hash() -> u64 {
+    let mut hasher = Fnv::new();
+
+    hasher.write_u64(world().tick());
+    // where the rng is, without moving it
+    hasher.write_u64(world().rng.clone().gen());
+
+    for (position, tile) in tile_map().iter() {
+        hasher.write_u64(position.0 as u64);
+        hasher.write_u64(position.1 as u64);
+        hasher.write_u64(tile.tile_type as u64);
+        hasher.write_u64(tile.facing.index() as u64);
+        hasher.write_u64(tile.animation.map_or(u64::MAX, |animation| animation as u64));
+        hasher.write_u64(tile.paused_at.unwrap_or(u64::MAX));
+    }
+
+    for (_, ground) in ground_map().iter() {
+        hasher.write_u64(ground.ground_type as u64);
+    }
+
+    for id in entities().ids() {
+        let entity = entities().get(id).unwrap();
+        hasher.write_u64(entity.id as u64);
+        hasher.write_u64(entity.entity_type as u64);
+        hasher.write_u64(entity.position.0 as u64);
+        hasher.write_u64(entity.position.1 as u64);
+    }
+
+    hasher.write_u64(player().position.0 as u64);
+    hasher.write_u64(player().position.1 as u64);
+
+    hasher.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{init_game_data, init_lua, init_world, World, GLOBALS_LOCK};
+
+    use super::*;
+
+    /// a recording which must keep replaying to the same world, made with --record
+    /// if a change to the core modules is meant to change the world, record it again
+    const REPLAY_FIXTURE_PATH: &str = r"tests/fixtures/replays/conveyor_line.jsonl";
+
+    #[test]
+    fn replay_reproduces_the_recorded_world_hash() {
+        let _globals = GLOBALS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let replay = Replay::load(REPLAY_FIXTURE_PATH).unwrap();
+        let (end_tick, expected_hash) = replay.end.expect("the fixture has no end line");
+
+        *world() = World::new(replay.seed);
+        let mut errors = init_lua();
+        errors.extend(init_game_data());
+        assert!(errors.is_empty(), "{:?}", errors);
+        init_world();
+
+        assert_eq!(run_replay(&replay), 0);
+        assert_eq!(world().tick(), end_tick);
+        assert_eq!(world_hash(), expected_hash);
+    }
+}
@@ -1,32 +1,38 @@
 use std::{collections::HashSet, fs::{self, DirEntry}, sync::{Arc, Mutex}};
 
+use bimap::BiMap;
 use crossterm::{cursor, event::{KeyCode, KeyEvent, KeyEventState, KeyModifiers}};
 use json::{object::Object, JsonValue};
-use rlua::{Context, Function, Lua, Table, ToLua, ToLuaMulti, Value};
+use rand::Rng;
+use rlua::{Context, Function, Lua, MultiValue, Table, ToLua, ToLuaMulti, Value};
 
-use crate::{dir_entry_is_dir, game_data_dump, identifier_dump, last_tick, lua, player, std_out, tile_map, time_between_ticks, write_to_debug, write_to_debug_pretty, Tile, CURSOR_POS, LAST_TICK, LUA, MAP_HEIGHT, MAP_LENGTH, MODULES_PATH, STATE_CHANGED, TIME_BETWEEN_TICKS};
+use crate::{sorted_dir, display::{ColorDisplay, TextDisplay}, ground_map, Ground, GroundType, ItemType, MappedGameData, TileType, command_help, message_log, ModuleLoadError, ModuleLoadErrorKind, Severity, command_registry, dir_entry_is_dir, find_command, register_command, run_command, entities, game_data_dump, identifier_dump, is_passable, map_position, mark_dirty, Direction, ByteStream, Entity, ThingType, VisibleThingType, lua, player, std_out, tile_map, Tile, CURSOR_POS, LUA, MAP_HEIGHT, MAP_LENGTH, MODULES_PATH, STATE_CHANGED, tick_clock, world};
 
-pub fn run_lua_scripts_from_path(path: &str, lua: Arc<Mutex<Lua>>) {
-    let dir = fs::read_dir(path).unwrap();
+/// returns the scripts which failed to run
+pub fn run_lua_scripts_from_path(path: &str, lua: Arc<Mutex<Lua>>) -> Vec<ModuleLoadError> {
+    let mut diagnostics = Vec::new();
+    let dir = sorted_dir(path).unwrap();
     
     for data in dir {
         let data = data.unwrap();
 
         lua.lock().unwrap().context(|lua_context| {
-            load_lua_script(Ok(&data), lua_context);
+            load_lua_script(Ok(&data), lua_context, &mut diagnostics);
         });
     }
 
     unsafe { 
         LUA = Some(lua)
     }
+
+    diagnostics
 }
 
-pub fn load_lua_script(data: Result<&DirEntry, &std::io::Error>, lua_context: Context) {
+pub fn load_lua_script(data: Result<&DirEntry, &std::io::Error>, lua_context: Context, diagnostics: &mut Vec<ModuleLoadError>) {
     if dir_entry_is_dir(data) {
-        let data = fs::read_dir(data.unwrap().path()).unwrap();
+        let data = sorted_dir(data.unwrap().path()).unwrap();
         for data in data {
-            load_lua_script(data.as_ref(), lua_context);
+            load_lua_script(data.as_ref(), lua_context, diagnostics);
         }
 
     } else {
@@ -38,7 +44,8 @@ pub fn load_lua_script(data: Result<&DirEntry, &std::io::Error>, lua_context: Co
                 let script_contents = fs::read_to_string(data.path()).unwrap();
 
                 if let Err(e) = lua_context.load(&script_contents).exec() {
-                    write_to_debug_pretty(format!("{}:\n{:?}", data.file_name().to_str().unwrap_or("Invalid utf-8"), e));
+                    log_error!("{}:\n{}", data.file_name().to_str().unwrap_or("Invalid utf-8"), e);
+                    diagnostics.push(ModuleLoadError::new(Some(&data.path()), ModuleLoadErrorKind::Script(e.to_string())));
                 }
 
             }
@@ -156,6 +163,22 @@ fn lua_to_json(lua_val: &rlua::Value<'_>) -> JsonValue {
     }
 }
 
+fn lua_value_to_string(value: Value) -> String {
+    match value {
+        Value::Nil => String::from("Nil"),
+        Value::Boolean(b) => format!("{}", b),
+        Value::LightUserData(l) => format!("{:?}", l),
+        Value::Integer(i) => format!("{}", i),
+        Value::Number(n) => format!("{}", n),
+        Value::String(s) => String::from(s.to_str().unwrap_or("invalid utf-8")),
+        Value::Table(t) => format!("{:?}", t),
+        Value::Function(f) => format!("{:?}", f),
+        Value::Thread(t) => format!("{:?}", t),
+        Value::UserData(u) => format!("{:?}", u),
+        Value::Error(e) => format!("{}", e),
+    }
+}
+
 pub fn load_default_lua_data(lua_context: Context) {
     let globals = lua_context.globals();
     let core = lua_context.create_table().unwrap();
@@ -165,26 +188,31 @@ pub fn load_default_lua_data(lua_context: Context) {
     // preset global variables
     // core
     {
-        let print_to_debug = lua_context.create_function(|_, text: Value| {
-            write_to_debug(match text {
-                Value::Nil => String::from("Nil"),
-                Value::Boolean(b) => format!("{}", b),
-                Value::LightUserData(l) => format!("{:?}", l),
-                Value::Integer(i) => format!("{}", i),
-                Value::Number(n) => format!("{}", n),
-                Value::String(s) => String::from(s.to_str().unwrap_or("invalid utf-8")),
-                Value::Table(t) => format!("{:?}", t),
-                Value::Function(f) => format!("{:?}", f),
-                Value::Thread(t) => format!("{:?}", t),
-                Value::UserData(u) => format!("{:?}", u),
-                Value::Error(e) => format!("{}", e),
-            });
+        let print_to_log = lua_context.create_function(|_, text: Value| {
+            message_log().push(Severity::Info, &lua_value_to_string(text));
+            Ok(())
+        }).unwrap();
+        core.set("print", print_to_log).unwrap();
+
+        let log = lua_context.create_function(|_, (level, text): (String, Value)| {
+            let severity = Severity::from_str(&level)
+                .ok_or_else(|| rlua::Error::RuntimeError(format!("'{}' isn't a log level, use debug, info, warn or error", level)))?;
+            message_log().push(severity, &lua_value_to_string(text));
             Ok(())
         }).unwrap();
-        core.set("print", print_to_debug).unwrap();
+        core.set("log", log).unwrap();
 
         let reload = lua_context.create_function(|_, ()|{
             let new_lua: Arc<Mutex<Lua>> = Arc::new(Mutex::new( Lua::new() ));
+            // the scripts register their commands again
+            command_registry().clear();
+            // entity states live in the old lua state, so their entities go with it
+            let removed = entities().ids().len();
+            if removed > 0 {
+                entities().clear();
+                message_log().push(Severity::Warn, &format!("reloading removed {} entities, their state was lost with the old scripts", removed));
+                unsafe { STATE_CHANGED = true; }
+            }
 
             new_lua.lock().unwrap().context(|lua_context|{
                 load_default_lua_data(lua_context);
@@ -205,7 +233,7 @@ pub fn load_default_lua_data(lua_context: Context) {
             let to_write = json::stringify(to_write);
             let res = fs::write(path, to_write);
             if let Err(e) = res {
-                write_to_debug(e);
+                log_error!("{}", e);
             }
             Ok(())
         }).unwrap();
@@ -231,6 +259,7 @@ pub fn load_default_lua_data(lua_context: Context) {
         terminal_table.set("cursorPos", cursor_pos_table).unwrap();
 
         let move_cursor = lua_context.create_function(|_, (x, y): (u16, u16)| {
+            refuse_while_stepping("Core.Terminal.moveCursor")?;
             ignorant_execute!(std_out, cursor::MoveToColumn(x));
             ignorant_execute!(std_out, cursor::MoveToRow(y));
             Ok(())
@@ -238,6 +267,7 @@ pub fn load_default_lua_data(lua_context: Context) {
         terminal_table.set("moveCursor", move_cursor).unwrap();
 
         let print_to_terminal = lua_context.create_function(|_, text: String| {
+            refuse_while_stepping("Core.Terminal.print")?;
             println!("{}", text);
             Ok(())
         }).unwrap();
@@ -250,8 +280,16 @@ pub fn load_default_lua_data(lua_context: Context) {
         let events_table = lua_context.create_table().unwrap();
         events_table.set("PostDeserializationEvents", lua_context.create_table().unwrap()).unwrap();
         events_table.set("TickEvents", lua_context.create_table().unwrap()).unwrap();
+        // once per frame after ticks ran, for drawing, unlike TickEvents these can use Core.Terminal
+        events_table.set("RenderEvents", lua_context.create_table().unwrap()).unwrap();
         events_table.set("KeyEvents", lua_context.create_table().unwrap()).unwrap();
         events_table.set("CommandEvents", lua_context.create_table().unwrap()).unwrap();
+        // gameplay events
+        events_table.set("TileChangeEvents", lua_context.create_table().unwrap()).unwrap();
+        events_table.set("TileBreakEvents", lua_context.create_table().unwrap()).unwrap();
+        events_table.set("TilePlaceEvents", lua_context.create_table().unwrap()).unwrap();
+        events_table.set("PlayerMoveEvents", lua_context.create_table().unwrap()).unwrap();
+        events_table.set("WorldGenEvents", lua_context.create_table().unwrap()).unwrap();
 
         core.set("Events", events_table).unwrap();
     }
@@ -278,9 +316,30 @@ pub fn load_default_lua_data(lua_context: Context) {
         }).unwrap();
         lua_player.set("getY", player_get_y).unwrap();
 
-        let player_set_pos = lua_context.create_function(|_, (x, y): (usize, usize)| {
+        // refuses to move into anything which isn't passable, returns if the player is at x, y afterwards
+        // and why not if it isn't
+        let player_set_pos = lua_context.create_function(|lua_context, (x, y): (i64, i64)| {
+            let (x, y) = match map_position(x, y) {
+                Ok(position) => position,
+                Err(e) => return Ok((false, Some(e))),
+            };
+            let old_position = player().position;
+            if old_position == (x, y) {
+                return Ok((true, None));
+            }
+            if !is_passable((x, y)) {
+                return Ok((false, Some(format!("({}, {}) isn't passable", x, y))));
+            }
             player().position = (x, y);
-            Ok(())
+
+            let luafied_move = lua_context.create_table().unwrap();
+            luafied_move.set("oldX", old_position.0).unwrap();
+            luafied_move.set("oldY", old_position.1).unwrap();
+            luafied_move.set("x", x).unwrap();
+            luafied_move.set("y", y).unwrap();
+
+            call_lua_events_with_context(lua_context, "PlayerMoveEvents", luafied_move);
+            Ok((true, None))
         }).unwrap();
         lua_player.set("setPosition", player_set_pos).unwrap();
 
@@ -290,75 +349,262 @@ pub fn load_default_lua_data(lua_context: Context) {
         let lua_map = lua_context.create_table().unwrap();
         let tile_map_table = lua_context.create_table().unwrap();
 
-        let tile_map_get = lua_context.create_function(|lua_context, (x, y): (usize, usize)|{
-            let requested = tile_map()[y][x];
+        // map functions return nil or false and a message for positions off the map
+        let tile_map_get = lua_context.create_function(|lua_context, (x, y): (i64, i64)|{
+            let requested = match lua_tile_at(x, y) {
+                Ok((_, tile)) => tile,
+                Err(e) => return Ok((None, Some(e))),
+            };
 
             let luafied_tile = lua_context.create_table().unwrap();
             luafied_tile.set("type", requested.tile_type).unwrap();
-
-            let luafied_text_display = lua_context.create_table().unwrap();
-            luafied_text_display.set("characterLeft", String::from(requested.text_display.character_left.unwrap_or(' '))).unwrap();
-            luafied_text_display.set("characterRight", String::from(requested.text_display.character_right.unwrap_or(' '))).unwrap();
-            luafied_tile.set("textDisplay", luafied_text_display).unwrap();
+            luafied_tile.set("identifier", identifier_dump().tile_types.get_by_right(&requested.tile_type).cloned()).unwrap();
+            luafied_tile.set("facing", requested.facing.label()).unwrap();
+            luafied_tile.set("animation", requested.animation_name()).unwrap();
+            luafied_tile.set("animationPaused", requested.paused_at.is_some()).unwrap();
+            luafied_tile.set("textDisplay", text_display_to_lua(lua_context, requested.text_display)).unwrap();
             //luafied_tile.set("colorDisplay", requested.color_display);
 
-            Ok(luafied_tile)
+            Ok((Some(luafied_tile), None))
         }).unwrap();
         tile_map_table.set("get", tile_map_get).unwrap();
 
-        let tile_map_set_from_id = lua_context.create_function(|_, (x, y, tile_id): (usize, usize, u16)| {
-            let tile = Tile::new(tile_id);
+        // cause is optional, "break" and "place" additionally fire their own events
+        // facing is optional, "north" by default and ignored for tiles which aren't rotatable
+        let tile_map_set_from_id = lua_context.create_function(|lua_context, (x, y, tile_id, cause, facing): (i64, i64, u16, Option<String>, Option<String>)| {
+            let ((x, y), old_tile) = match lua_tile_at(x, y) {
+                Ok(found) => found,
+                Err(e) => return Ok((false, Some(e))),
+            };
+            let facing = match facing.as_deref().map(Direction::from_str) {
+                Some(Some(facing)) => facing,
+                Some(None) => return Ok((false, Some(format!("'{}' isn't a direction", facing.unwrap())))),
+                None => Direction::North,
+            };
+            let tile = Tile::facing(tile_id, facing);
             match tile {
                 Some(tile) => {
-                    tile_map()[y][x] = tile;
-                    return Ok(true)
+                    let old_type = old_tile.tile_type;
+                    tile_map().set((x, y), tile);
+
+                    let luafied_change = tile_change_to_lua(lua_context, (x, y), old_type, tile_id, cause.as_deref());
+
+                    call_lua_events_with_context(lua_context, "TileChangeEvents", luafied_change.clone());
+                    match cause.as_deref() {
+                        Some("break") => call_lua_events_with_context(lua_context, "TileBreakEvents", luafied_change),
+                        Some("place") => call_lua_events_with_context(lua_context, "TilePlaceEvents", luafied_change),
+                        _ => {}
+                    }
+                    return Ok((true, None))
                 },
-                None => return Ok(false),
+                None => return Ok((false, Some(format!("{} isn't a tile type", tile_id)))),
             }
         }).unwrap();
         tile_map_table.set("setFromId", tile_map_set_from_id).unwrap();
+
+        // turns is optional, 1 by default, a quarter clockwise each and counter clockwise when negative
+        // fires TileChangeEvents with the cause "rotate"
+        let tile_map_rotate = lua_context.create_function(|lua_context, (x, y, turns): (i64, i64, Option<i64>)| {
+            let ((x, y), mut tile) = match lua_tile_at(x, y) {
+                Ok(found) => found,
+                Err(e) => return Ok((false, Some(e))),
+            };
+            if !tile.rotate(turns.unwrap_or(1)) {
+                return Ok((false, Some(format!("the tile at ({}, {}) isn't rotatable", x, y))));
+            }
+            tile_map().set((x, y), tile);
+
+            let luafied_change = tile_change_to_lua(lua_context, (x, y), tile.tile_type, tile.tile_type, Some("rotate"));
+            luafied_change.set("facing", tile.facing.label()).unwrap();
+            call_lua_events_with_context(lua_context, "TileChangeEvents", luafied_change);
+            Ok((true, None))
+        }).unwrap();
+        tile_map_table.set("rotate", tile_map_rotate).unwrap();
+
+        // name is one of the tile type's animations, nil goes back to the animation for its facing
+        let tile_map_set_animation = lua_context.create_function(|_, (x, y, name): (i64, i64, Option<String>)| {
+            let ((x, y), mut tile) = match lua_tile_at(x, y) {
+                Ok(found) => found,
+                Err(e) => return Ok((false, Some(e))),
+            };
+            if !tile.set_animation(name.as_deref()) {
+                let identifier = identifier_dump().tile_types.get_by_right(&tile.tile_type).cloned().unwrap_or_default();
+                return Ok((false, Some(format!("'{}' isn't an animation of {}", name.unwrap(), identifier))));
+            }
+            tile_map().set((x, y), tile);
+            mark_dirty((x, y));
+            Ok((true, None))
+        }).unwrap();
+        tile_map_table.set("setAnimation", tile_map_set_animation).unwrap();
+
+        // paused tiles stay on their current frame
+        let tile_map_set_animation_paused = lua_context.create_function(|_, (x, y, paused): (i64, i64, bool)| {
+            let ((x, y), mut tile) = match lua_tile_at(x, y) {
+                Ok(found) => found,
+                Err(e) => return Ok((false, Some(e))),
+            };
+            tile.set_paused(paused);
+            tile_map().set((x, y), tile);
+            mark_dirty((x, y));
+            Ok((true, None))
+        }).unwrap();
+        tile_map_table.set("setAnimationPaused", tile_map_set_animation_paused).unwrap();
         lua_map.set("TileMap", tile_map_table).unwrap();
 
+        let ground_map_table = lua_context.create_table().unwrap();
+
+        let ground_map_get = lua_context.create_function(|lua_context, (x, y): (i64, i64)| {
+            let requested = match lua_ground_at(x, y) {
+                Ok((_, ground)) => ground,
+                Err(e) => return Ok((None, Some(e))),
+            };
+
+            let luafied_ground = lua_context.create_table().unwrap();
+            luafied_ground.set("type", requested.ground_type).unwrap();
+            luafied_ground.set("identifier", identifier_dump().ground_types.get_by_right(&requested.ground_type).cloned()).unwrap();
+            luafied_ground.set("textDisplay", text_display_to_lua(lua_context, requested.text_display)).unwrap();
+
+            Ok((Some(luafied_ground), None))
+        }).unwrap();
+        ground_map_table.set("get", ground_map_get).unwrap();
+
+        let ground_map_set = lua_context.create_function(|_, (x, y, ground_id): (i64, i64, u16)| {
+            let (position, _) = match lua_ground_at(x, y) {
+                Ok(found) => found,
+                Err(e) => return Ok((false, Some(e))),
+            };
+            match Ground::new(ground_id) {
+                Some(ground) => {
+                    ground_map().set(position, ground);
+                    Ok((true, None))
+                },
+                None => Ok((false, Some(format!("{} isn't a ground type", ground_id)))),
+            }
+        }).unwrap();
+        ground_map_table.set("set", ground_map_set).unwrap();
+        lua_map.set("GroundMap", ground_map_table).unwrap();
+
+        // combines ground, tile and entity collision, false off the map
+        let map_is_passable = lua_context.create_function(|_, (x, y): (i64, i64)| {
+            match map_position(x, y) {
+                Ok(position) => Ok((is_passable(position), None)),
+                Err(e) => Ok((false, Some(e))),
+            }
+        }).unwrap();
+        lua_map.set("isPassable", map_is_passable).unwrap();
+
         lua_map.set("width", MAP_LENGTH - 1).unwrap();
         lua_map.set("height", MAP_HEIGHT - 1).unwrap();
 
         game_info_table.set("Map", lua_map).unwrap();
 
-        // tile
+        // tile, ground and item types
+        // Types.get(id) and Types.all() give type tables, Identifiers.get(identifier) and Identifiers.fromId(id) convert between the two
         let tile_table = lua_context.create_table().unwrap();
-        let tile_types_table = lua_context.create_table().unwrap();
-        let tile_idents_table = lua_context.create_table().unwrap();
-
-        // tile type
-        let tile_type_get = lua_context.create_function(|lua_context, tile_id: u16| {
-            let luafied_tile_type = lua_context.create_table().unwrap();
-            if let Some(tile_type) = game_data_dump().tile_types.get(&tile_id) {
-                luafied_tile_type.set("solid", tile_type.solid).unwrap();
+        let (tile_types_table, tile_idents_table) = create_type_tables(lua_context, "tile", || &game_data_dump().tile_types, || &identifier_dump().tile_types, tile_type_to_lua);
+        tile_table.set("Types", tile_types_table).unwrap();
+        tile_table.set("Identifiers", tile_idents_table).unwrap();
+        game_info_table.set("Tile", tile_table).unwrap();
+
+        let ground_table = lua_context.create_table().unwrap();
+        let (ground_types_table, ground_idents_table) = create_type_tables(lua_context, "ground", || &game_data_dump().ground_types, || &identifier_dump().ground_types, ground_type_to_lua);
+        ground_table.set("Types", ground_types_table).unwrap();
+        ground_table.set("Identifiers", ground_idents_table).unwrap();
+        game_info_table.set("Ground", ground_table).unwrap();
+
+        let item_table = lua_context.create_table().unwrap();
+        let (item_types_table, item_idents_table) = create_type_tables(lua_context, "item", || &game_data_dump().item_types, || &identifier_dump().item_types, item_type_to_lua);
+        item_table.set("Types", item_types_table).unwrap();
+        item_table.set("Identifiers", item_idents_table).unwrap();
+        game_info_table.set("Item", item_table).unwrap();
+
+        // things, visible things are included with visible = true
+        lua_context.set_named_registry_value(THING_HANDLERS_KEY, lua_context.create_table().unwrap()).unwrap();
+        let things_table = lua_context.create_table().unwrap();
+
+        let things_get = lua_context.create_function(|lua_context, identifier: String| {
+            let identifiers = identifier_dump();
+            if let Some(id) = identifiers.thing_types.get_by_left(&identifier) {
+                return Ok(Some(thing_to_lua(lua_context, &identifier, &game_data_dump().thing_types[id])));
             }
-            
-            Ok(luafied_tile_type)
+            if let Some(id) = identifiers.visible_thing_types.get_by_left(&identifier) {
+                return Ok(Some(visible_thing_to_lua(lua_context, &identifier, &game_data_dump().visible_thing_types[id])));
+            }
+            Ok(None)
         }).unwrap();
-        tile_types_table.set("get", tile_type_get).unwrap();
-        tile_table.set("Types", tile_types_table).unwrap();
-        
-        // tile ident
-        let tile_ident_get = lua_context.create_function(|_, internal_name: String| {
-            let ret = identifier_dump().tile_types.get_by_left(&internal_name);
-            let ret = match ret {
-                Some(id) => Value::Number(*id as f64),
-                None => Value::Nil,
-            };
+        things_table.set("get", things_get).unwrap();
+
+        let things_of_type = lua_context.create_function(|lua_context, type_identifier: String| {
+            let things: Vec<Table> = mapped_things(lua_context).into_iter()
+                .filter(|(thing_type, _)| *thing_type == type_identifier)
+                .map(|(_, luafied_thing)| luafied_thing)
+                .collect();
+            lua_context.create_sequence_from(things)
+        }).unwrap();
+        things_table.set("ofType", things_of_type).unwrap();
 
-            Ok(ret)
+        let things_register_handler = lua_context.create_function(|lua_context, (type_identifier, handler): (String, Function)| {
+            register_handler(lua_context, THING_HANDLERS_KEY, type_identifier, handler)
         }).unwrap();
-        tile_idents_table.set("get", tile_ident_get).unwrap();
-        tile_table.set("Identifiers", tile_idents_table).unwrap();
+        things_table.set("registerHandler", things_register_handler).unwrap();
+
+        game_info_table.set("Things", things_table).unwrap();
+
+        // byte streams
+        lua_context.set_named_registry_value(BYTE_STREAM_HANDLERS_KEY, lua_context.create_table().unwrap()).unwrap();
+        let byte_streams_table = lua_context.create_table().unwrap();
+
+        let byte_streams_get = lua_context.create_function(|lua_context, identifier: String| {
+            Ok(identifier_dump().byte_streams.get_by_left(&identifier)
+                .map(|id| byte_stream_to_lua(lua_context, &identifier, &game_data_dump().byte_streams[id])))
+        }).unwrap();
+        byte_streams_table.set("get", byte_streams_get).unwrap();
+
+        let byte_streams_of_type = lua_context.create_function(|lua_context, type_identifier: String| {
+            let byte_streams: Vec<Table> = mapped_byte_streams(lua_context).into_iter()
+                .filter(|(stream_type, _)| stream_type.as_ref() == Some(&type_identifier))
+                .map(|(_, luafied_stream)| luafied_stream)
+                .collect();
+            lua_context.create_sequence_from(byte_streams)
+        }).unwrap();
+        byte_streams_table.set("ofType", byte_streams_of_type).unwrap();
+
+        let byte_streams_register_handler = lua_context.create_function(|lua_context, (type_identifier, handler): (String, Function)| {
+            register_handler(lua_context, BYTE_STREAM_HANDLERS_KEY, type_identifier, handler)
+        }).unwrap();
+        byte_streams_table.set("registerHandler", byte_streams_register_handler).unwrap();
+
+        game_info_table.set("ByteStreams", byte_streams_table).unwrap();
 
-        game_info_table.set("Tile", tile_table).unwrap();
-        
 
         core.set("GameInfo", game_info_table).unwrap();
     }
+    // commands
+    {
+        let commands_table = lua_context.create_table().unwrap();
+
+        let register = lua_context.create_function(|lua_context, (name, spec): (String, Table)| {
+            register_command(lua_context, name, spec).map_err(rlua::Error::RuntimeError)
+        }).unwrap();
+        commands_table.set("register", register).unwrap();
+
+        let run = lua_context.create_function(|lua_context, (name, input): (String, Table)| {
+            Ok(run_command(lua_context, &name, input))
+        }).unwrap();
+        commands_table.set("run", run).unwrap();
+
+        let exists = lua_context.create_function(|_, name: String| {
+            Ok(find_command(&name).is_some())
+        }).unwrap();
+        commands_table.set("exists", exists).unwrap();
+
+        let help = lua_context.create_function(|_, name: Option<String>| {
+            Ok(command_help(name.as_deref()))
+        }).unwrap();
+        commands_table.set("help", help).unwrap();
+
+        core.set("Commands", commands_table).unwrap();
+    }
     // ui render
     {
         let buffer_map_redraw = lua_context.create_function(|_, ()| {
@@ -371,17 +617,201 @@ pub fn load_default_lua_data(lua_context: Context) {
     // tick
     {
         let tick_table = lua_context.create_table().unwrap();
-        let add_time = lua_context.create_function(|_, amount: u32| {
-            unsafe {
-                LAST_TICK = Some(last_tick() + (time_between_ticks() * amount));
-            }
-            
+
+        // how many ticks have run since the game started, never goes down
+        let current = lua_context.create_function(|_, ()| {
+            Ok(world().tick())
+        }).unwrap();
+        tick_table.set("current", current).unwrap();
+
+        // the clock is real time, so like Core.Terminal ticks can't change it
+        // runs amount extra ticks as soon as possible, also while paused
+        for name in ["step", "addTime"] {
+            let step = lua_context.create_function(move |_, amount: u64| {
+                refuse_while_stepping(&format!("Core.Tick.{}", name))?;
+                tick_clock().step(amount);
+                Ok(())
+            }).unwrap();
+            tick_table.set(name, step).unwrap();
+        }
+
+        let is_paused = lua_context.create_function(|_, ()| {
+            Ok(tick_clock().is_paused())
+        }).unwrap();
+        tick_table.set("isPaused", is_paused).unwrap();
+
+        let set_paused = lua_context.create_function(|_, paused: bool| {
+            refuse_while_stepping("Core.Tick.setPaused")?;
+            tick_clock().set_paused(paused);
             Ok(())
         }).unwrap();
-        tick_table.set("addTime", add_time).unwrap();
+        tick_table.set("setPaused", set_paused).unwrap();
+
+        let get_speed = lua_context.create_function(|_, ()| {
+            Ok(tick_clock().speed())
+        }).unwrap();
+        tick_table.set("getSpeed", get_speed).unwrap();
+
+        // returns false and a message if speed isn't more than 0 and at most MAX_SPEED
+        let set_speed = lua_context.create_function(|_, speed: f64| {
+            refuse_while_stepping("Core.Tick.setSpeed")?;
+            match tick_clock().set_speed(speed) {
+                Ok(_) => Ok((true, None)),
+                Err(e) => Ok((false, Some(e))),
+            }
+        }).unwrap();
+        tick_table.set("setSpeed", set_speed).unwrap();
 
         core.set("Tick", tick_table).unwrap();
     }
+    // random
+    // everything random in the simulation comes from the world's seeded rng, so runs with the same seed are the same
+    {
+        let random_table = lua_context.create_table().unwrap();
+
+        // min and max are included
+        let integer = lua_context.create_function(|_, (min, max): (i64, i64)| {
+            if min > max {
+                return Err(rlua::Error::RuntimeError(format!("min {} is more than max {}", min, max)));
+            }
+            Ok(world().rng.gen_range(min..=max))
+        }).unwrap();
+        random_table.set("integer", integer).unwrap();
+
+        // from 0 up to but not including 1
+        let float = lua_context.create_function(|_, ()| {
+            Ok(world().rng.gen::<f64>())
+        }).unwrap();
+        random_table.set("float", float).unwrap();
+
+        let seed = lua_context.create_function(|_, ()| {
+            Ok(world().seed() as i64)
+        }).unwrap();
+        random_table.set("seed", seed).unwrap();
+
+        core.set("Random", random_table).unwrap();
+
+        // math.random works like it normally does, but from the world's rng
+        let math: Table = globals.get("math").unwrap();
+        let math_random = lua_context.create_function(|_, (m, n): (Option<i64>, Option<i64>)| {
+            let (min, max) = match (m, n) {
+                (None, _) => return Ok(Value::Number(world().rng.gen::<f64>())),
+                (Some(m), None) => (1, m),
+                (Some(m), Some(n)) => (m, n),
+            };
+            if min > max {
+                return Err(rlua::Error::RuntimeError(String::from("bad argument to 'random' (interval is empty)")));
+            }
+            Ok(Value::Integer(world().rng.gen_range(min..=max)))
+        }).unwrap();
+        math.set("random", math_random).unwrap();
+
+        let math_randomseed = lua_context.create_function(|_, seed: i64| {
+            world().reseed(seed as u64);
+            Ok(())
+        }).unwrap();
+        math.set("randomseed", math_randomseed).unwrap();
+
+        // ticks can't depend on real time
+        let os: Table = globals.get("os").unwrap();
+        for name in ["time", "clock", "date"] {
+            guard_real_time(lua_context, &os, name);
+        }
+    }
+    // entities
+    // they don't survive Core.reload, their states are kept in the lua state it replaces
+    {
+        lua_context.set_named_registry_value(ENTITY_STATES_KEY, lua_context.create_table().unwrap()).unwrap();
+        lua_context.set_named_registry_value(ENTITY_TICK_HANDLERS_KEY, lua_context.create_table().unwrap()).unwrap();
+        let entities_table = lua_context.create_table().unwrap();
+
+        // like the map functions these return nil or false and a message when they fail
+        // state is optional, it's kept for as long as the entity exists
+        let spawn = lua_context.create_function(|lua_context, (type_identifier, x, y, state): (String, i64, i64, Option<Table>)| {
+            let entity_type = match identifier_dump().entity_types.get_by_left(&type_identifier) {
+                Some(entity_type) => *entity_type,
+                None => return Ok((None, Some(format!("'{}' isn't an entity type", type_identifier)))),
+            };
+            let position = match map_position(x, y) {
+                Ok(position) => position,
+                Err(e) => return Ok((None, Some(e))),
+            };
+            let id = match entities().spawn(entity_type, position) {
+                Some(id) => id,
+                None => return Ok((None, Some(format!("couldn't spawn '{}'", type_identifier)))),
+            };
+
+            let states: Table = lua_context.named_registry_value(ENTITY_STATES_KEY)?;
+            states.set(id, match state {
+                Some(state) => state,
+                None => lua_context.create_table()?,
+            })?;
+
+            unsafe { STATE_CHANGED = true; }
+            Ok((Some(id), None))
+        }).unwrap();
+        entities_table.set("spawn", spawn).unwrap();
+
+        let get = lua_context.create_function(|lua_context, id: u32| {
+            match entities().get(id) {
+                Some(entity) => Ok((Some(entity_to_lua(lua_context, entity)), None)),
+                None => Ok((None, Some(no_entity_message(id)))),
+            }
+        }).unwrap();
+        entities_table.set("get", get).unwrap();
+
+        let remove = lua_context.create_function(|lua_context, id: u32| {
+            if entities().remove(id).is_none() {
+                return Ok((false, Some(no_entity_message(id))));
+            }
+
+            let states: Table = lua_context.named_registry_value(ENTITY_STATES_KEY)?;
+            states.set(id, Value::Nil)?;
+
+            unsafe { STATE_CHANGED = true; }
+            Ok((true, None))
+        }).unwrap();
+        entities_table.set("remove", remove).unwrap();
+
+        let set_position = lua_context.create_function(|_, (id, x, y): (u32, i64, i64)| {
+            let position = match map_position(x, y) {
+                Ok(position) => position,
+                Err(e) => return Ok((false, Some(e))),
+            };
+            if !entities().set_position(id, position) {
+                return Ok((false, Some(no_entity_message(id))));
+            }
+
+            unsafe { STATE_CHANGED = true; }
+            Ok((true, None))
+        }).unwrap();
+        entities_table.set("setPosition", set_position).unwrap();
+
+        // rect is { x = _, y = _, width = _, height = _ }, the part of it off the map is ignored
+        let query = lua_context.create_function(|lua_context, rect: Table| {
+            let (x, y) = (rect.get::<_, i64>("x")?, rect.get::<_, i64>("y")?);
+            let (width, height) = (rect.get::<_, i64>("width")?, rect.get::<_, i64>("height")?);
+            // clamped to the map before casting, so huge or negative numbers can't overflow
+            let (left, right) = (x.clamp(0, MAP_LENGTH as i64), x.saturating_add(width).clamp(0, MAP_LENGTH as i64));
+            let (top, bottom) = (y.clamp(0, MAP_HEIGHT as i64), y.saturating_add(height).clamp(0, MAP_HEIGHT as i64));
+            let top_left = (left as usize, top as usize);
+            let size = ((right - left).max(0) as usize, (bottom - top).max(0) as usize);
+
+            let found: Vec<Table> = entities().in_rect(top_left, size)
+                .map(|entity| entity_to_lua(lua_context, entity))
+                .collect();
+            lua_context.create_sequence_from(found)
+        }).unwrap();
+        entities_table.set("query", query).unwrap();
+
+        // the handler gets the entity every tick, in the same form as get
+        let register_tick = lua_context.create_function(|lua_context, (type_identifier, handler): (String, Function)| {
+            register_handler(lua_context, ENTITY_TICK_HANDLERS_KEY, type_identifier, handler)
+        }).unwrap();
+        entities_table.set("registerTick", register_tick).unwrap();
+
+        core.set("Entities", entities_table).unwrap();
+    }
 
     globals.set("Core", core).unwrap();
 }
@@ -450,7 +880,7 @@ pub fn call_key_events(event: &KeyEvent) {
                     for pair in key_events.pairs::<Value, Function>() {
                         let pair = pair.unwrap();
                         if let Err(e) = pair.1.call::<Table, ()>(luafied_event.clone()) {
-                            write_to_debug_pretty(format!("{:?}:\n{:?}", pair.0, e));
+                            log_error!("{:?}:\n{}", pair.0, e);
                         }
                     }
                 }
@@ -460,19 +890,354 @@ pub fn call_key_events(event: &KeyEvent) {
 }
 
 pub fn call_lua_events<T: for<'a> ToLuaMulti<'a> + Clone>(event_key: &str, args: T) {
-
     lua().lock().unwrap().context(|lua_context| {
-        let globals = lua_context.globals();
+        call_lua_events_with_context(lua_context, event_key, args);
+    });
+}
 
-        if let Ok(core) = globals.get::<_, Table>("Core") {
-            if let Ok(events) = core.get::<_, Table>("Events") {
-                if let Ok(events_table) = events.get::<_, Table>(event_key) {
-    
-                    for pair in events_table.pairs::<Value, Function>() {
-                        pair.unwrap().1.call::<T, ()>(args.clone()).unwrap();
+/// errors if the world is stepping, for things ticks can't depend on or do
+fn refuse_while_stepping(what: &str) -> rlua::Result<()> {
+    if world().is_stepping() {
+        Err(rlua::Error::RuntimeError(format!("{} can't be used during a tick, use RenderEvents instead", what)))
+    } else {
+        Ok(())
+    }
+}
+
+/// wraps os[name] so it can't be used during a tick, the original is kept in the registry
+fn guard_real_time<'lua>(lua_context: Context<'lua>, os: &Table<'lua>, name: &'static str) {
+    let original: Function = match os.get(name) {
+        Ok(original) => original,
+        Err(_) => return,
+    };
+    let registry_key = format!("real_time_os_{}", name);
+    lua_context.set_named_registry_value(&registry_key, original).unwrap();
+
+    let guarded = lua_context.create_function(move |lua_context, args: MultiValue| {
+        refuse_while_stepping(&format!("os.{}", name))?;
+        let original: Function = lua_context.named_registry_value(&registry_key)?;
+        original.call::<_, MultiValue>(args)
+    }).unwrap();
+    os.set(name, guarded).unwrap();
+}
+
+/// for firing events from inside lua callbacks, where the lua mutex is already held
+pub fn call_lua_events_with_context<'lua, T: ToLuaMulti<'lua> + Clone>(lua_context: Context<'lua>, event_key: &str, args: T) {
+    let globals = lua_context.globals();
+
+    if let Ok(core) = globals.get::<_, Table>("Core") {
+        if let Ok(events) = core.get::<_, Table>("Events") {
+            if let Ok(events_table) = events.get::<_, Table>(event_key) {
+
+                for pair in events_table.pairs::<Value, Function>() {
+                    let pair = pair.unwrap();
+                    if let Err(e) = pair.1.call::<T, ()>(args.clone()) {
+                        log_error!("{:?}:\n{}", pair.0, e);
                     }
                 }
             }
         }
+    }
+}
+
+/// payload for TileChangeEvents, TileBreakEvents and TilePlaceEvents
+fn tile_change_to_lua<'lua>(lua_context: Context<'lua>, (x, y): (usize, usize), old_type: u16, new_type: u16, cause: Option<&str>) -> Table<'lua> {
+    let luafied_change = lua_context.create_table().unwrap();
+    luafied_change.set("x", x).unwrap();
+    luafied_change.set("y", y).unwrap();
+    luafied_change.set("oldType", old_type).unwrap();
+    luafied_change.set("newType", new_type).unwrap();
+    luafied_change.set("oldIdentifier", identifier_dump().tile_types.get_by_right(&old_type).cloned()).unwrap();
+    luafied_change.set("newIdentifier", identifier_dump().tile_types.get_by_right(&new_type).cloned()).unwrap();
+    luafied_change.set("cause", cause).unwrap();
+
+    luafied_change
+}
+
+/// handlers registered with Core.GameInfo.Things.registerHandler, by type identifier
+const THING_HANDLERS_KEY: &str = "thing_handlers";
+/// handlers registered with Core.GameInfo.ByteStreams.registerHandler, by type identifier
+const BYTE_STREAM_HANDLERS_KEY: &str = "byte_stream_handlers";
+
+fn register_handler<'lua>(lua_context: Context<'lua>, registry_key: &str, type_identifier: String, handler: Function<'lua>) -> rlua::Result<()> {
+    let handlers: Table = lua_context.named_registry_value(registry_key)?;
+    let type_handlers = match handlers.get::<_, Option<Table>>(type_identifier.as_str())? {
+        Some(type_handlers) => type_handlers,
+        None => {
+            let type_handlers = lua_context.create_table()?;
+            handlers.set(type_identifier, type_handlers.clone())?;
+            type_handlers
+        },
+    };
+    type_handlers.set(type_handlers.len()? + 1, handler)
+}
+
+/// the tile at a position given by lua, or the message to return instead
+fn lua_tile_at(x: i64, y: i64) -> Result<((usize, usize), Tile), String> {
+    let position = map_position(x, y)?;
+    match tile_map().get(position) {
+        Some(tile) => Ok((position, *tile)),
+        None => Err(format!("there's no tile at ({}, {})", x, y)),
+    }
+}
+
+/// the ground at a position given by lua, or the message to return instead
+fn lua_ground_at(x: i64, y: i64) -> Result<((usize, usize), Ground), String> {
+    let position = map_position(x, y)?;
+    match ground_map().get(position) {
+        Some(ground) => Ok((position, *ground)),
+        None => Err(format!("there's no ground at ({}, {})", x, y)),
+    }
+}
+
+fn text_display_to_lua(lua_context: Context<'_>, text_display: TextDisplay) -> Table<'_> {
+    let luafied_text_display = lua_context.create_table().unwrap();
+    luafied_text_display.set("characterLeft", String::from(text_display.character_left.unwrap_or(' '))).unwrap();
+    luafied_text_display.set("characterRight", String::from(text_display.character_right.unwrap_or(' '))).unwrap();
+    luafied_text_display
+}
+
+/// colors are { r, g, b } tables, nil where unset
+fn color_display_to_lua(lua_context: Context<'_>, color_display: ColorDisplay) -> Table<'_> {
+    let luafied_color_display = lua_context.create_table().unwrap();
+    let colors = [
+        ("textColorLeft", color_display.text_color_left),
+        ("backColorLeft", color_display.back_color_left),
+        ("textColorRight", color_display.text_color_right),
+        ("backColorRight", color_display.back_color_right),
+    ];
+    for (key, color) in colors {
+        if let Some((r, g, b)) = color {
+            luafied_color_display.set(key, vec![r, g, b]).unwrap();
+        }
+    }
+    luafied_color_display
+}
+
+/// the fields every type table has
+fn type_to_lua<'lua>(lua_context: Context<'lua>, identifiers: &BiMap<String, u16>, id: u16, name: &str, text_display: TextDisplay, color_display: ColorDisplay) -> Table<'lua> {
+    let luafied_type = lua_context.create_table().unwrap();
+    luafied_type.set("id", id).unwrap();
+    luafied_type.set("identifier", identifiers.get_by_right(&id).cloned()).unwrap();
+    luafied_type.set("name", name).unwrap();
+    luafied_type.set("textDisplay", text_display_to_lua(lua_context, text_display)).unwrap();
+    luafied_type.set("colorDisplay", color_display_to_lua(lua_context, color_display)).unwrap();
+    luafied_type
+}
+
+fn tile_type_to_lua<'lua>(lua_context: Context<'lua>, tile_type: &TileType) -> Table<'lua> {
+    let luafied_type = type_to_lua(lua_context, &identifier_dump().tile_types, tile_type.identifier, &tile_type.name, tile_type.text_display, tile_type.color_display);
+    luafied_type.set("solid", tile_type.solid).unwrap();
+    luafied_type.set("worldGenWeight", tile_type.world_gen_weight).unwrap();
+    luafied_type.set("rotatable", tile_type.rotatable).unwrap();
+    let animation_names = tile_type.animations.iter().map(|(name, _)| name.as_str());
+    luafied_type.set("animations", lua_context.create_sequence_from(animation_names).unwrap()).unwrap();
+    luafied_type
+}
+
+fn ground_type_to_lua<'lua>(lua_context: Context<'lua>, ground_type: &GroundType) -> Table<'lua> {
+    let luafied_type = type_to_lua(lua_context, &identifier_dump().ground_types, ground_type.identifier, &ground_type.name, ground_type.text_display, ground_type.color_display);
+    luafied_type.set("solid", ground_type.solid).unwrap();
+    luafied_type.set("worldGenWeight", ground_type.world_gen_weight).unwrap();
+    luafied_type
+}
+
+fn item_type_to_lua<'lua>(lua_context: Context<'lua>, item_type: &ItemType) -> Table<'lua> {
+    type_to_lua(lua_context, &identifier_dump().item_types, item_type.identifier, &item_type.name, item_type.text_display, item_type.color_display)
+}
+
+/// the Types and Identifiers tables for one kind of type
+/// the game data is only read when they're called, so this can run before it's loaded
+fn create_type_tables<'lua, T: 'static>(
+    lua_context: Context<'lua>,
+    type_name: &'static str,
+    types: fn() -> &'static MappedGameData<T>,
+    identifiers: fn() -> &'static BiMap<String, u16>,
+    to_lua: for<'a> fn(Context<'a>, &T) -> Table<'a>,
+) -> (Table<'lua>, Table<'lua>) {
+    let types_table = lua_context.create_table().unwrap();
+    let idents_table = lua_context.create_table().unwrap();
+
+    let types_get = lua_context.create_function(move |lua_context, id: u16| {
+        match types().get(&id) {
+            Some(found) => Ok((Some(to_lua(lua_context, found)), None)),
+            None => Ok((None, Some(format!("{} isn't a {} type", id, type_name)))),
+        }
+    }).unwrap();
+    types_table.set("get", types_get).unwrap();
+
+    // ordered by id
+    let types_all = lua_context.create_function(move |lua_context, ()| {
+        let mut ids: Vec<&u16> = types().keys().collect();
+        ids.sort();
+        lua_context.create_sequence_from(ids.into_iter().map(|id| to_lua(lua_context, &types()[id])))
+    }).unwrap();
+    types_table.set("all", types_all).unwrap();
+
+    let idents_get = lua_context.create_function(move |_, identifier: String| {
+        Ok(identifiers().get_by_left(&identifier).cloned())
+    }).unwrap();
+    idents_table.set("get", idents_get).unwrap();
+
+    let idents_from_id = lua_context.create_function(move |_, id: u16| {
+        Ok(identifiers().get_by_right(&id).cloned())
+    }).unwrap();
+    idents_table.set("fromId", idents_from_id).unwrap();
+
+    (types_table, idents_table)
+}
+
+fn no_entity_message(id: u32) -> String {
+    format!("there's no entity {}", id)
+}
+
+/// state tables of every entity, by id
+const ENTITY_STATES_KEY: &str = "entity_states";
+/// handlers registered with Core.Entities.registerTick, by type identifier
+const ENTITY_TICK_HANDLERS_KEY: &str = "entity_tick_handlers";
+
+fn entity_to_lua<'lua>(lua_context: Context<'lua>, entity: &Entity) -> Table<'lua> {
+    let states: Table = lua_context.named_registry_value(ENTITY_STATES_KEY).unwrap();
+
+    let luafied_entity = lua_context.create_table().unwrap();
+    luafied_entity.set("id", entity.id).unwrap();
+    luafied_entity.set("type", entity.entity_type).unwrap();
+    luafied_entity.set("identifier", identifier_dump().entity_types.get_by_right(&entity.entity_type).cloned()).unwrap();
+    luafied_entity.set("x", entity.position.0).unwrap();
+    luafied_entity.set("y", entity.position.1).unwrap();
+    // an entity without a state gets an empty one, which is kept like any other
+    let state = match states.get::<_, Option<Table>>(entity.id).unwrap() {
+        Some(state) => state,
+        None => {
+            let state = lua_context.create_table().unwrap();
+            states.set(entity.id, state.clone()).unwrap();
+            state
+        },
+    };
+    luafied_entity.set("state", state).unwrap();
+
+    luafied_entity
+}
+
+/// runs the tick handlers of every entity's type, entities spawned during this don't tick until the next one
+pub fn call_entity_tick_handlers() {
+    lua().lock().unwrap().context(|lua_context| {
+        for id in entities().ids() {
+            // removed by an earlier handler
+            let entity = match entities().get(id) {
+                Some(entity) => *entity,
+                None => continue,
+            };
+            if let Some(type_identifier) = identifier_dump().entity_types.get_by_right(&entity.entity_type) {
+                call_handlers(lua_context, ENTITY_TICK_HANDLERS_KEY, type_identifier, entity_to_lua(lua_context, &entity));
+            }
+        }
     });
-}
\ No newline at end of file
+}
+
+/// bytes as a table of numbers, plus as a string if they're valid utf-8
+fn set_lua_bytes(luafied: &Table, bytes: &[u8]) {
+    luafied.set("bytes", bytes.to_vec()).unwrap();
+    luafied.set("text", std::str::from_utf8(bytes).ok()).unwrap();
+}
+
+fn thing_to_lua<'lua>(lua_context: Context<'lua>, identifier: &str, thing_type: &ThingType) -> Table<'lua> {
+    let luafied_thing = lua_context.create_table().unwrap();
+    luafied_thing.set("identifier", identifier).unwrap();
+    luafied_thing.set("id", thing_type.identifier).unwrap();
+    luafied_thing.set("typeIdentifier", thing_type.type_identifier.as_str()).unwrap();
+    luafied_thing.set("visible", false).unwrap();
+    set_lua_bytes(&luafied_thing, &thing_type.bytes);
+
+    luafied_thing
+}
+
+fn visible_thing_to_lua<'lua>(lua_context: Context<'lua>, identifier: &str, thing_type: &VisibleThingType) -> Table<'lua> {
+    let luafied_thing = lua_context.create_table().unwrap();
+    luafied_thing.set("identifier", identifier).unwrap();
+    luafied_thing.set("id", thing_type.identifier).unwrap();
+    luafied_thing.set("typeIdentifier", thing_type.type_identifier.as_str()).unwrap();
+    luafied_thing.set("visible", true).unwrap();
+    set_lua_bytes(&luafied_thing, &thing_type.bytes);
+
+    luafied_thing.set("textDisplay", text_display_to_lua(lua_context, thing_type.text_display)).unwrap();
+
+    luafied_thing
+}
+
+fn byte_stream_to_lua<'lua>(lua_context: Context<'lua>, identifier: &str, byte_stream: &ByteStream) -> Table<'lua> {
+    let luafied_stream = lua_context.create_table().unwrap();
+    luafied_stream.set("identifier", identifier).unwrap();
+    luafied_stream.set("id", byte_stream.identifier).unwrap();
+    luafied_stream.set("typeIdentifier", byte_stream.type_identifier.as_deref()).unwrap();
+    set_lua_bytes(&luafied_stream, &byte_stream.bytes);
+
+    luafied_stream
+}
+
+/// every thing and visible thing as (type identifier, lua table), things first, each ordered by id
+fn mapped_things(lua_context: Context<'_>) -> Vec<(String, Table<'_>)> {
+    let mut things: Vec<(&String, &u16)> = identifier_dump().thing_types.iter().collect();
+    things.sort_by_key(|(_, id)| **id);
+    let mut visible_things: Vec<(&String, &u16)> = identifier_dump().visible_thing_types.iter().collect();
+    visible_things.sort_by_key(|(_, id)| **id);
+
+    let mut luafied_things = Vec::new();
+    for (identifier, id) in things {
+        let thing_type = &game_data_dump().thing_types[id];
+        luafied_things.push((thing_type.type_identifier.clone(), thing_to_lua(lua_context, identifier, thing_type)));
+    }
+    for (identifier, id) in visible_things {
+        let thing_type = &game_data_dump().visible_thing_types[id];
+        luafied_things.push((thing_type.type_identifier.clone(), visible_thing_to_lua(lua_context, identifier, thing_type)));
+    }
+    luafied_things
+}
+
+/// every byte stream as (type identifier, lua table), ordered by id
+fn mapped_byte_streams(lua_context: Context<'_>) -> Vec<(Option<String>, Table<'_>)> {
+    let mut byte_streams: Vec<(&String, &u16)> = identifier_dump().byte_streams.iter().collect();
+    byte_streams.sort_by_key(|(_, id)| **id);
+
+    byte_streams.into_iter().map(|(identifier, id)| {
+        let byte_stream = &game_data_dump().byte_streams[id];
+        (byte_stream.type_identifier.clone(), byte_stream_to_lua(lua_context, identifier, byte_stream))
+    }).collect()
+}
+
+fn call_handlers<'lua>(lua_context: Context<'lua>, registry_key: &str, type_identifier: &str, instance: Table<'lua>) {
+    let handlers: Table = lua_context.named_registry_value(registry_key).unwrap();
+    if let Ok(Some(type_handlers)) = handlers.get::<_, Option<Table>>(type_identifier) {
+        for handler in type_handlers.sequence_values::<Function>() {
+            if let Err(e) = handler.unwrap().call::<_, ()>(instance.clone()) {
+                log_error!("handler for '{}':\n{}", type_identifier, e);
+            }
+        }
+    }
+}
+
+/// passes every mapped thing and byte stream to the handlers of its type identifier
+/// called once the game data has been mapped
+pub fn call_data_handlers() {
+    lua().lock().unwrap().context(|lua_context| {
+        for (type_identifier, luafied_thing) in mapped_things(lua_context) {
+            call_handlers(lua_context, THING_HANDLERS_KEY, &type_identifier, luafied_thing);
+        }
+        for (type_identifier, luafied_stream) in mapped_byte_streams(lua_context) {
+            if let Some(type_identifier) = type_identifier {
+                call_handlers(lua_context, BYTE_STREAM_HANDLERS_KEY, &type_identifier, luafied_stream);
+            }
+        }
+    });
+}
+
+/// fired once the tile and ground maps have been generated
+pub fn call_world_gen_events() {
+    lua().lock().unwrap().context(|lua_context| {
+        let luafied_world = lua_context.create_table().unwrap();
+        luafied_world.set("width", MAP_LENGTH).unwrap();
+        luafied_world.set("height", MAP_HEIGHT).unwrap();
+
+        call_lua_events_with_context(lua_context, "WorldGenEvents", luafied_world);
+    });
+}
@@ -0,0 +1,169 @@
+use std::{fs, time::{Duration, Instant}};
+
+use json::JsonValue;
+use once_cell::sync::Lazy;
+
+use crate::CONFIG_PATH;
+
+/// the fastest speed can be set to, far past what a frame can keep up with anyway
+pub const MAX_SPEED: f64 = 1000.0;
+
+pub static mut TICK_CLOCK: Lazy<TickClock> = Lazy::new(|| TickClock::new(TickConfig::default()));
+/// safe unsafe action lolz
+// only stepped and changed from the main thread
+#[allow(static_mut_refs)]
+pub fn tick_clock() -> &'static mut TickClock { unsafe { &mut TICK_CLOCK } }
+
+/// How fast ticks run, read from "tick" in conveyor_line_engine_config
+#[derive(Clone, Debug)]
+pub struct TickConfig {
+    pub ticks_per_second: f64,
+    /// the most ticks run in one frame, anything past it is dropped so a slow frame can't snowball
+    pub max_catch_up_ticks: u64,
+}
+
+impl Default for TickConfig {
+    fn default() -> Self {
+        Self {
+            ticks_per_second: 20.0,
+            max_catch_up_ticks: 10,
+        }
+    }
+}
+
+impl TickConfig {
+    /// missing or malformed fields keep their defaults
+    pub fn from_json(tick_config: &JsonValue) -> Self {
+        let mut config = Self::default();
+
+        if let Some(ticks_per_second) = tick_config["ticks_per_second"].as_f64() {
+            if ticks_per_second > 0.0 && ticks_per_second.is_finite() {
+                config.ticks_per_second = ticks_per_second;
+            }
+        }
+        if let Some(max_catch_up_ticks) = tick_config["max_catch_up_ticks"].as_u64() {
+            config.max_catch_up_ticks = max_catch_up_ticks.max(1);
+        }
+
+        config
+    }
+}
+
+/// configures the tick clock from the engine config, falling back to the defaults
+pub fn init_tick_clock() {
+    let config = fs::read_to_string(CONFIG_PATH).ok()
+        .and_then(|config| json::parse(&config).ok())
+        .map(|config| TickConfig::from_json(&config["conveyor_line_engine_config"]["tick"]))
+        .unwrap_or_default();
+
+    tick_clock().configure(config);
+}
+
+/// Fixed timestep clock, real time is added up and paid out as whole ticks
+/// it only decides when the world steps, the tick count belongs to the World
+pub struct TickClock {
+    config: TickConfig,
+    /// multiplies how fast real time turns into ticks
+    speed: f64,
+    paused: bool,
+    /// real time which hasn't become a tick yet, already multiplied by speed
+    accumulated: Duration,
+    last_update: Option<Instant>,
+    /// ticks asked for by step, which run even while paused
+    queued: u64,
+}
+
+impl TickClock {
+    pub fn new(config: TickConfig) -> Self {
+        Self {
+            config,
+            speed: 1.0,
+            paused: false,
+            accumulated: Duration::ZERO,
+            last_update: None,
+            queued: 0,
+        }
+    }
+
+    pub fn configure(&mut self, config: TickConfig) {
+        self.config = config;
+    }
+
+    /// real time between ticks at speed 1
+    pub fn tick_duration(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.config.ticks_per_second)
+    }
+
+    /// how many ticks should run now, call once per frame
+    pub fn due_ticks(&mut self, now: Instant) -> u64 {
+        let elapsed = match self.last_update {
+            Some(last_update) => now.saturating_duration_since(last_update),
+            None => Duration::ZERO,
+        };
+        self.last_update = Some(now);
+
+        let from_time = if self.paused {
+            0
+        } else {
+            self.accumulated += elapsed.mul_f64(self.speed);
+            let accumulated = self.accumulated.as_nanos();
+            let tick_duration = self.tick_duration().as_nanos().max(1);
+            self.accumulated = Duration::from_nanos((accumulated % tick_duration) as u64);
+            (accumulated / tick_duration) as u64
+        };
+
+        // time past the cap is dropped, queued ticks wait for the next frame instead
+        let from_time = from_time.min(self.config.max_catch_up_ticks);
+        let from_queue = self.queued.min(self.config.max_catch_up_ticks - from_time);
+        self.queued -= from_queue;
+
+        from_time + from_queue
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// time which passes while paused never turns into ticks
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.accumulated = Duration::ZERO;
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// the error is meant to be shown to the player
+    pub fn set_speed(&mut self, speed: f64) -> Result<(), String> {
+        if speed > 0.0 && speed <= MAX_SPEED {
+            self.speed = speed;
+            Ok(())
+        } else {
+            Err(format!("speed must be more than 0 and at most {}, not {}", MAX_SPEED, speed))
+        }
+    }
+
+    /// runs amount extra ticks as soon as possible, also while paused
+    pub fn step(&mut self, amount: u64) {
+        self.queued = self.queued.saturating_add(amount);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huge_speeds_are_refused() {
+        let mut clock = TickClock::new(TickConfig::default());
+        assert!(clock.set_speed(1e300).is_err());
+        assert!(clock.set_speed(f64::NAN).is_err());
+        assert!(clock.set_speed(MAX_SPEED).is_ok());
+
+        // a day long frame at the fastest speed still only runs the capped amount
+        let start = Instant::now();
+        clock.due_ticks(start);
+        assert_eq!(clock.due_ticks(start + Duration::from_secs(60 * 60 * 24)), TickConfig::default().max_catch_up_ticks);
+    }
+}
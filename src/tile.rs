@@ -1,39 +1,151 @@
 use crate::{
-    display::*, game_data_dump
+    display::*, game_data_dump, tick_count
 };
 
 /// Represents a tile, which is a solid or non-solid element of the map
 #[derive(Clone, Copy, Debug)]
 pub struct Tile {
     pub tile_type: u16,
+    /// always North for tiles which aren't rotatable
+    pub facing: Direction,
+    /// index into TileType::animations, None plays the animation for its facing
+    pub animation: Option<u16>,
+    /// the tick its animation was paused on, it stays on that frame until resumed
+    pub paused_at: Option<u64>,
+    /// the frame currently shown
     pub text_display: TextDisplay,
     pub color_display: ColorDisplay,
 }
 
 impl Tile {
     pub fn new_unchecked(tile_type: u16) -> Self {
-        let tile_from_map = game_data_dump().tile_types.get(&tile_type).unwrap();
-
-        Self {
-            tile_type,
-            text_display: tile_from_map.text_display,
-            color_display: tile_from_map.color_display
-        }
+        Self::new(tile_type).unwrap()
     }
     pub fn new(tile_type: u16) -> Option<Self> {
+        Self::facing(tile_type, Direction::North)
+    }
+    /// facing is ignored if the tile type isn't rotatable
+    pub fn facing(tile_type: u16, facing: Direction) -> Option<Self> {
         match game_data_dump().tile_types.get(&tile_type) {
             Some(tile_from_map) => {
+                let facing = if tile_from_map.rotatable { facing } else { Direction::North };
+                let (text_display, color_display) = tile_from_map.facing_animations[facing.index()].frame_at(tick_count());
+
                 Some(
                     Self {
                         tile_type,
-                        text_display: tile_from_map.text_display,
-                        color_display: tile_from_map.color_display
+                        facing,
+                        animation: None,
+                        paused_at: None,
+                        text_display,
+                        color_display
                     }
                 )
             },
             None => None,
         }
     }
+    /// turns a quarter clockwise, counter clockwise for negative turns
+    /// returns false if the tile type isn't rotatable
+    pub fn rotate(&mut self, turns: i64) -> bool {
+        match game_data_dump().tile_types.get(&self.tile_type) {
+            Some(tile_from_map) if tile_from_map.rotatable => {
+                self.facing = self.facing.rotated(turns);
+                self.animate(tick_count());
+                true
+            },
+            _ => false,
+        }
+    }
+    /// shows the frame for tick, or for the tick it was paused on
+    /// returns true if what is shown changed, so the cell needs to be drawn again
+    pub fn animate(&mut self, tick: u64) -> bool {
+        let tile_from_map = match game_data_dump().tile_types.get(&self.tile_type) {
+            Some(tile_from_map) => tile_from_map,
+            None => return false,
+        };
+        let animation = match self.animation {
+            Some(animation) => &tile_from_map.animations[animation as usize].1,
+            None => &tile_from_map.facing_animations[self.facing.index()],
+        };
+
+        let frame = animation.frame_at(self.paused_at.unwrap_or(tick));
+        let changed = frame != (self.text_display, self.color_display);
+        (self.text_display, self.color_display) = frame;
+        changed
+    }
+    /// None goes back to the animation for its facing
+    /// returns false if the tile type has no animation called name
+    pub fn set_animation(&mut self, name: Option<&str>) -> bool {
+        let animation = match name {
+            Some(name) => {
+                let tile_from_map = game_data_dump().tile_types.get(&self.tile_type).unwrap();
+                match tile_from_map.animations.iter().position(|(animation_name, _)| animation_name == name) {
+                    Some(animation) => Some(animation as u16),
+                    None => return false,
+                }
+            },
+            None => None,
+        };
+        self.animation = animation;
+        self.animate(tick_count());
+        true
+    }
+    pub fn animation_name(&self) -> Option<&'static str> {
+        let tile_from_map = game_data_dump().tile_types.get(&self.tile_type)?;
+        Some(tile_from_map.animations[self.animation? as usize].0.as_str())
+    }
+    /// stops on the current frame, resuming jumps to the frame every other tile of the type is on
+    pub fn set_paused(&mut self, paused: bool) {
+        match (paused, self.paused_at) {
+            (true, None) => self.paused_at = Some(tick_count()),
+            (false, Some(_)) => self.paused_at = None,
+            _ => {},
+        }
+        self.animate(tick_count());
+    }
+}
+
+/// Which way something faces on the map
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// in clockwise order, the same order as TileType::facing_displays
+    pub const ALL: [Direction; 4] = [Self::North, Self::East, Self::South, Self::West];
+
+    pub fn from_str(direction: &str) -> Option<Self> {
+        match direction.to_lowercase().as_str() {
+            "north" => Some(Self::North),
+            "east" => Some(Self::East),
+            "south" => Some(Self::South),
+            "west" => Some(Self::West),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::North => "north",
+            Self::East => "east",
+            Self::South => "south",
+            Self::West => "west",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// turned a quarter clockwise per turn, counter clockwise for negative turns
+    pub fn rotated(&self, turns: i64) -> Self {
+        Self::ALL[(self.index() as i64 + turns.rem_euclid(4)).rem_euclid(4) as usize]
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -51,6 +163,12 @@ pub struct TileType {
     pub color_display: ColorDisplay,
     pub solid: bool,
     pub world_gen_weight: f64,
+    /// if placed tiles can face any Direction
+    pub rotatable: bool,
+    /// how the tile looks facing each Direction, all the same if it isn't rotatable
+    pub facing_animations: [Animation; 4],
+    /// other animations tiles can switch to, sorted by name, they look the same in every Direction
+    pub animations: Vec<(String, Animation)>,
 }
 
 // HasColor implements
@@ -70,4 +188,15 @@ impl HasTextDisplay for Tile {
     fn text_display (&self) -> TextDisplay {
         self.text_display
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotating_by_any_amount_doesnt_overflow() {
+        assert_eq!(Direction::West.rotated(i64::MAX), Direction::South);
+        assert_eq!(Direction::North.rotated(i64::MIN), Direction::North);
+        assert_eq!(Direction::North.rotated(-1), Direction::West);
+    }
+}
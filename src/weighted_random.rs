@@ -1,4 +1,4 @@
-use rand::{rngs::ThreadRng, Rng, thread_rng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 /*
 #######################################
@@ -44,7 +44,7 @@ struct Entry<T: Clone> {
 pub struct WeightedRandom<T: Clone> {
     entries: Vec<Entry<T>>,
     accumulated_weight: f64,
-    rng: ThreadRng,
+    rng: StdRng,
 }
 
 impl <T: Clone>  WeightedRandom<T> {
@@ -63,15 +63,20 @@ impl <T: Clone>  WeightedRandom<T> {
 pub struct WeightedRandomBuilder<T: Clone> {
     entries: Vec<Entry<T>>,
     accumulated_weight: f64,
-    rng: ThreadRng,
+    rng: StdRng,
 }
 
 impl <T: Clone> WeightedRandomBuilder<T> {
-    pub fn new() -> WeightedRandomBuilder<T> {
+    /// the same seed and entries always give the same results
+    pub fn seeded(seed: u64) -> WeightedRandomBuilder<T> {
+        Self::with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: StdRng) -> WeightedRandomBuilder<T> {
         WeightedRandomBuilder {
             entries: vec![],
             accumulated_weight: 0f64,
-            rng: thread_rng(),
+            rng,
         }
     }
 
@@ -0,0 +1,72 @@
+use once_cell::sync::Lazy;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{call_entity_tick_handlers, call_lua_events};
+
+pub static mut WORLD: Lazy<World> = Lazy::new(|| World::new(0));
+/// safe unsafe action lolz
+// the simulation only runs on the main thread
+#[allow(static_mut_refs)]
+pub fn world() -> &'static mut World { unsafe { &mut WORLD } }
+
+/// Simulation state which isn't on the map, the maps, entities and player are the rest
+pub struct World {
+    /// what rng was last seeded with, running again with it gives the same world
+    seed: u64,
+    /// the only randomness the simulation may use, Core.Random and math.random in lua
+    pub rng: StdRng,
+    /// steps taken since the world was made, only ever goes up
+    tick: u64,
+    /// set while step runs, so things which depend on real time can refuse
+    stepping: bool,
+}
+
+impl World {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            tick: 0,
+            stepping: false,
+        }
+    }
+
+    /// a seed which is different every run, for when none is given
+    /// kept below i64::MAX so lua can hold it
+    pub fn random_seed() -> u64 {
+        rand::thread_rng().gen_range(0..=i64::MAX as u64)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// starts rng over from seed, the tick count is kept
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn is_stepping(&self) -> bool {
+        self.stepping
+    }
+}
+
+/// advances the simulation by one tick, running TickEvents and the entity tick handlers
+/// it only depends on the world and lua state and never on real time, so the same inputs always give the same world.
+/// how many steps run and when is up to the TickClock
+/// the world isn't passed in because the handlers reach it through world() from lua while this runs,
+/// anything which depends on real time refuses while is_stepping instead
+pub fn step() {
+    world().stepping = true;
+
+    call_lua_events("TickEvents", ());
+    call_entity_tick_handlers();
+    world().tick += 1;
+
+    world().stepping = false;
+}